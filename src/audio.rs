@@ -0,0 +1,566 @@
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Whether a device plays sound or records it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeviceKind {
+    Output,
+    Input,
+}
+
+/// A sink or source known to the sound server
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioDevice {
+    pub name: String,
+    pub description: String,
+    /// Average volume over all channels in percent
+    pub volume: u32,
+    pub muted: bool,
+    pub is_default: bool,
+}
+
+/// Access to the sound server, kept behind a trait so the UI can run against a fake
+pub trait AudioBackend {
+    fn devices(&self, kind: DeviceKind) -> Vec<AudioDevice>;
+    fn set_volume(&self, kind: DeviceKind, device: &str, volume: u32) -> io::Result<()>;
+    fn set_mute(&self, kind: DeviceKind, device: &str, muted: bool) -> io::Result<()>;
+    fn set_default(&self, kind: DeviceKind, device: &str) -> io::Result<()>;
+
+    /// Calls `on_event` whenever devices may have changed, backends without events are only
+    /// read again after a change made through them
+    fn subscribe(&self, _on_event: Box<dyn Fn() + Send>) {}
+}
+
+/// Outputs and inputs, read together on the audio thread
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioDevices {
+    pub outputs: Vec<AudioDevice>,
+    pub inputs: Vec<AudioDevice>,
+}
+
+impl AudioDevices {
+    fn read(backend: &dyn AudioBackend) -> Self {
+        Self {
+            outputs: backend.devices(DeviceKind::Output),
+            inputs: backend.devices(DeviceKind::Input),
+        }
+    }
+
+    pub fn of_kind(&self, kind: DeviceKind) -> &[AudioDevice] {
+        match kind {
+            DeviceKind::Output => &self.outputs,
+            DeviceKind::Input => &self.inputs,
+        }
+    }
+
+    /// The device new streams go to, if any
+    pub fn default_device(&self, kind: DeviceKind) -> Option<&AudioDevice> {
+        self.of_kind(kind).iter().find(|device| device.is_default)
+    }
+}
+
+/// A change made on the audio thread, reporting its own failure
+type AudioChange = Box<dyn FnOnce(&dyn AudioBackend) + Send>;
+
+enum AudioRequest {
+    /// The sound server reported an event
+    Read,
+    Change(AudioChange),
+}
+
+/// Hands changes to the thread started by [`watch_audio`], which owns the backend
+#[derive(Clone)]
+pub struct AudioWatcher(mpsc::Sender<AudioRequest>);
+
+impl std::fmt::Debug for AudioWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AudioWatcher")
+    }
+}
+
+impl AudioWatcher {
+    /// Runs `change` on the audio thread after the ones sent before it. The devices are
+    /// reported afterwards even if nothing changed, so a failed change is undone on screen.
+    pub fn change(
+        &self,
+        change: impl FnOnce(&dyn AudioBackend) -> io::Result<()> + Send + 'static,
+        on_error: impl FnOnce(io::Error) + Send + 'static,
+    ) {
+        let _ = self.0.send(AudioRequest::Change(Box::new(move |backend| {
+            if let Err(e) = change(backend) {
+                on_error(e);
+            }
+        })));
+    }
+}
+
+/// Reads the devices on a thread whenever the backend reports an event, calling `on_change`
+/// when they changed. `pactl` can take a while when the sound server is busy or restarting,
+/// which must not stall the bar.
+pub fn watch_audio(
+    backend: Box<dyn AudioBackend + Send>,
+    on_change: impl Fn(AudioDevices) + Send + 'static,
+) -> AudioWatcher {
+    let (sender, receiver) = mpsc::channel();
+    let events = sender.clone();
+    backend.subscribe(Box::new(move || {
+        let _ = events.send(AudioRequest::Read);
+    }));
+    std::thread::spawn(move || {
+        let mut last = None;
+        let mut changed = true;
+        loop {
+            let devices = AudioDevices::read(backend.as_ref());
+            if changed || last.as_ref() != Some(&devices) {
+                on_change(devices.clone());
+                last = Some(devices);
+            }
+            let Ok(request) = receiver.recv() else {
+                return;
+            };
+            // Apply everything queued up, e.g. a burst of scroll steps, before reading again
+            changed = false;
+            for request in std::iter::once(request).chain(receiver.try_iter()) {
+                if let AudioRequest::Change(change) = request {
+                    change(backend.as_ref());
+                    changed = true;
+                }
+            }
+        }
+    });
+    AudioWatcher(sender)
+}
+
+/// Volume after scrolling `step` percent up or down, kept between 0 and 100
+pub fn stepped_volume(device: &AudioDevice, step: i32) -> u32 {
+    (device.volume as i32 + step).clamp(0, 100) as u32
+}
+
+/// Sets a device's volume unless it already has it, sliders report every small move
+pub fn change_volume(
+    backend: &dyn AudioBackend,
+    kind: DeviceKind,
+    device: &AudioDevice,
+    volume: u32,
+) -> io::Result<()> {
+    if device.volume == volume {
+        return Ok(());
+    }
+    backend.set_volume(kind, &device.name, volume)
+}
+
+pub fn toggle_mute(
+    backend: &dyn AudioBackend,
+    kind: DeviceKind,
+    device: &AudioDevice,
+) -> io::Result<()> {
+    backend.set_mute(kind, &device.name, !device.muted)
+}
+
+/// Makes new streams use the device, nothing to do if they already do
+pub fn make_default(
+    backend: &dyn AudioBackend,
+    kind: DeviceKind,
+    device: &AudioDevice,
+) -> io::Result<()> {
+    if device.is_default {
+        return Ok(());
+    }
+    backend.set_default(kind, &device.name)
+}
+
+/// Delay before `pactl subscribe` is started again after the sound server went away
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Talks to PulseAudio or pipewire-pulse through `pactl`
+pub struct PactlBackend;
+
+impl PactlBackend {
+    fn pactl(args: &[&str]) -> io::Result<String> {
+        let output = Command::new("pactl").args(args).output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn noun(kind: DeviceKind) -> &'static str {
+        match kind {
+            DeviceKind::Output => "sink",
+            DeviceKind::Input => "source",
+        }
+    }
+}
+
+impl AudioBackend for PactlBackend {
+    fn devices(&self, kind: DeviceKind) -> Vec<AudioDevice> {
+        let noun = Self::noun(kind);
+        let default = Self::pactl(&[&format!("get-default-{}", noun)])
+            .map(|name| name.trim().to_string())
+            .unwrap_or_default();
+        let Ok(json) = Self::pactl(&["--format=json", "list", &format!("{}s", noun)]) else {
+            return Vec::new();
+        };
+        let Ok(Value::Array(devices)) = serde_json::from_str::<Value>(&json) else {
+            return Vec::new();
+        };
+
+        devices
+            .iter()
+            // Monitor sources mirror an output and are not microphones
+            .filter(|device| device["monitor_of_sink"].as_str().is_none())
+            .filter_map(|device| {
+                let name = device["name"].as_str()?.to_string();
+                Some(AudioDevice {
+                    description: device["description"].as_str().unwrap_or(&name).to_string(),
+                    volume: average_volume(&device["volume"]),
+                    muted: device["mute"].as_bool().unwrap_or(false),
+                    is_default: name == default,
+                    name,
+                })
+            })
+            .collect()
+    }
+
+    fn set_volume(&self, kind: DeviceKind, device: &str, volume: u32) -> io::Result<()> {
+        let noun = Self::noun(kind);
+        Self::pactl(&[
+            &format!("set-{}-volume", noun),
+            device,
+            &format!("{}%", volume),
+        ])
+        .map(|_| ())
+    }
+
+    fn set_mute(&self, kind: DeviceKind, device: &str, muted: bool) -> io::Result<()> {
+        let noun = Self::noun(kind);
+        Self::pactl(&[
+            &format!("set-{}-mute", noun),
+            device,
+            if muted { "1" } else { "0" },
+        ])
+        .map(|_| ())
+    }
+
+    fn set_default(&self, kind: DeviceKind, device: &str) -> io::Result<()> {
+        let noun = Self::noun(kind);
+        Self::pactl(&[&format!("set-default-{}", noun), device]).map(|_| ())
+    }
+
+    fn subscribe(&self, on_event: Box<dyn Fn() + Send>) {
+        std::thread::spawn(move || {
+            loop {
+                let mut child = match Command::new("pactl")
+                    .arg("subscribe")
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()
+                {
+                    Ok(child) => child,
+                    Err(e) => {
+                        eprintln!("Failed to watch the sound server: {}", e);
+                        return;
+                    }
+                };
+                if let Some(stdout) = child.stdout.take() {
+                    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                        if is_device_event(&line) {
+                            on_event();
+                        }
+                    }
+                }
+                let _ = child.wait();
+                // The sound server restarted or is not up yet, devices are read again once
+                // it is back
+                std::thread::sleep(RESUBSCRIBE_DELAY);
+                on_event();
+            }
+        });
+    }
+}
+
+/// Whether a `pactl subscribe` line can change a device, e.g. "Event 'change' on sink #52".
+/// Streams starting and stopping ("on sink-input") are left out.
+fn is_device_event(line: &str) -> bool {
+    let Some((_, facility)) = line.split_once(" on ") else {
+        return false;
+    };
+    let facility = facility.split(' ').next().unwrap_or_default();
+    matches!(facility, "sink" | "source" | "server" | "card")
+}
+
+/// Averages the "value_percent" of every channel, e.g. {"front-left": {"value_percent": "40%"}}
+fn average_volume(volume: &Value) -> u32 {
+    let Some(channels) = volume.as_object() else {
+        return 0;
+    };
+    let percents: Vec<u32> = channels
+        .values()
+        .filter_map(|channel| {
+            channel["value_percent"]
+                .as_str()?
+                .trim_end_matches('%')
+                .parse()
+                .ok()
+        })
+        .collect();
+    if percents.is_empty() {
+        0
+    } else {
+        percents.iter().sum::<u32>() / percents.len() as u32
+    }
+}
+
+/// Symbolic icon for the given volume and mute state
+pub fn volume_icon(device: Option<&AudioDevice>) -> &'static str {
+    match device {
+        None => "audio-volume-muted-symbolic",
+        Some(device) if device.muted || device.volume == 0 => "audio-volume-muted-symbolic",
+        Some(device) if device.volume < 34 => "audio-volume-low-symbolic",
+        Some(device) if device.volume < 67 => "audio-volume-medium-symbolic",
+        Some(_) => "audio-volume-high-symbolic",
+    }
+}
+
+/// Symbolic icon for the given microphone state
+pub fn microphone_icon(device: Option<&AudioDevice>) -> &'static str {
+    match device {
+        Some(device) if !device.muted && device.volume > 0 => {
+            "microphone-sensitivity-high-symbolic"
+        }
+        _ => "microphone-sensitivity-muted-symbolic",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Keeps devices in memory and records every change made through it
+    #[derive(Default)]
+    struct FakeBackend {
+        devices: Mutex<Vec<(DeviceKind, AudioDevice)>>,
+        calls: Mutex<Vec<String>>,
+        fail: bool,
+    }
+
+    impl FakeBackend {
+        fn with_devices(devices: &[(DeviceKind, &str, u32, bool)]) -> Self {
+            let devices = devices
+                .iter()
+                .map(|&(kind, name, volume, is_default)| {
+                    let device = AudioDevice {
+                        name: name.to_string(),
+                        description: name.to_string(),
+                        volume,
+                        muted: false,
+                        is_default,
+                    };
+                    (kind, device)
+                })
+                .collect();
+            Self {
+                devices: Mutex::new(devices),
+                ..Default::default()
+            }
+        }
+
+        fn change(
+            &self,
+            kind: DeviceKind,
+            call: String,
+            apply: impl Fn(&mut AudioDevice),
+        ) -> io::Result<()> {
+            self.calls.lock().unwrap().push(call);
+            if self.fail {
+                return Err(io::Error::other("Connection refused"));
+            }
+            for (device_kind, device) in self.devices.lock().unwrap().iter_mut() {
+                if *device_kind == kind {
+                    apply(device);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl AudioBackend for FakeBackend {
+        fn devices(&self, kind: DeviceKind) -> Vec<AudioDevice> {
+            self.devices
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(device_kind, _)| *device_kind == kind)
+                .map(|(_, device)| device.clone())
+                .collect()
+        }
+
+        fn set_volume(&self, kind: DeviceKind, name: &str, volume: u32) -> io::Result<()> {
+            self.change(kind, format!("volume {} {}", name, volume), |device| {
+                if device.name == name {
+                    device.volume = volume;
+                }
+            })
+        }
+
+        fn set_mute(&self, kind: DeviceKind, name: &str, muted: bool) -> io::Result<()> {
+            self.change(kind, format!("mute {} {}", name, muted), |device| {
+                if device.name == name {
+                    device.muted = muted;
+                }
+            })
+        }
+
+        fn set_default(&self, kind: DeviceKind, name: &str) -> io::Result<()> {
+            self.change(kind, format!("default {}", name), |device| {
+                device.is_default = device.name == name;
+            })
+        }
+    }
+
+    fn default_output(backend: &FakeBackend) -> AudioDevice {
+        let devices = AudioDevices::read(backend);
+        devices.default_device(DeviceKind::Output).unwrap().clone()
+    }
+
+    #[test]
+    fn default_device_is_per_kind() {
+        let backend = FakeBackend::with_devices(&[
+            (DeviceKind::Output, "speakers", 40, true),
+            (DeviceKind::Input, "mic", 80, true),
+        ]);
+        let devices = AudioDevices::read(&backend);
+        assert_eq!(
+            devices.default_device(DeviceKind::Output).unwrap().name,
+            "speakers"
+        );
+        assert_eq!(
+            devices.default_device(DeviceKind::Input).unwrap().name,
+            "mic"
+        );
+    }
+
+    #[test]
+    fn default_device_is_none_without_default() {
+        let backend = FakeBackend::with_devices(&[(DeviceKind::Output, "speakers", 40, false)]);
+        let devices = AudioDevices::read(&backend);
+        assert!(devices.default_device(DeviceKind::Output).is_none());
+    }
+
+    #[test]
+    fn stepped_volume_stays_in_range() {
+        let backend = FakeBackend::with_devices(&[(DeviceKind::Output, "speakers", 98, true)]);
+        let device = default_output(&backend);
+        assert_eq!(stepped_volume(&device, 5), 100);
+        assert_eq!(stepped_volume(&device, -5), 93);
+
+        let quiet = AudioDevice {
+            volume: 3,
+            ..device
+        };
+        assert_eq!(stepped_volume(&quiet, -5), 0);
+    }
+
+    #[test]
+    fn change_volume_skips_unchanged() {
+        let backend = FakeBackend::with_devices(&[(DeviceKind::Output, "speakers", 40, true)]);
+        let device = default_output(&backend);
+        change_volume(&backend, DeviceKind::Output, &device, 40).unwrap();
+        assert!(backend.calls.lock().unwrap().is_empty());
+
+        change_volume(&backend, DeviceKind::Output, &device, 55).unwrap();
+        assert_eq!(*backend.calls.lock().unwrap(), ["volume speakers 55"]);
+        assert_eq!(default_output(&backend).volume, 55);
+    }
+
+    #[test]
+    fn toggle_mute_flips_state() {
+        let backend = FakeBackend::with_devices(&[(DeviceKind::Output, "speakers", 40, true)]);
+        toggle_mute(&backend, DeviceKind::Output, &default_output(&backend)).unwrap();
+        assert!(default_output(&backend).muted);
+        toggle_mute(&backend, DeviceKind::Output, &default_output(&backend)).unwrap();
+        assert!(!default_output(&backend).muted);
+    }
+
+    #[test]
+    fn make_default_moves_default() {
+        let backend = FakeBackend::with_devices(&[
+            (DeviceKind::Output, "speakers", 40, true),
+            (DeviceKind::Output, "headphones", 20, false),
+        ]);
+        let headphones = backend.devices(DeviceKind::Output)[1].clone();
+        make_default(&backend, DeviceKind::Output, &headphones).unwrap();
+        assert_eq!(default_output(&backend).name, "headphones");
+
+        // Already the default, so the sound server is left alone
+        make_default(&backend, DeviceKind::Output, &default_output(&backend)).unwrap();
+        assert_eq!(backend.calls.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn errors_are_returned() {
+        let backend = FakeBackend {
+            fail: true,
+            ..FakeBackend::with_devices(&[(DeviceKind::Output, "speakers", 40, true)])
+        };
+        let device = default_output(&backend);
+        assert!(change_volume(&backend, DeviceKind::Output, &device, 10).is_err());
+        assert!(toggle_mute(&backend, DeviceKind::Output, &device).is_err());
+        assert_eq!(default_output(&backend).volume, 40);
+    }
+
+    #[test]
+    fn average_volume_over_channels() {
+        let volume = serde_json::json!({
+            "front-left": {"value_percent": "40%"},
+            "front-right": {"value_percent": "60%"},
+        });
+        assert_eq!(average_volume(&volume), 50);
+        assert_eq!(average_volume(&Value::Null), 0);
+    }
+
+    #[test]
+    fn device_events_are_recognised() {
+        assert!(is_device_event("Event 'change' on sink #52"));
+        assert!(is_device_event("Event 'new' on source #3"));
+        assert!(is_device_event("Event 'change' on server #4294967295"));
+        assert!(!is_device_event("Event 'new' on sink-input #118"));
+        assert!(!is_device_event("Event 'remove' on source-output #7"));
+        assert!(!is_device_event(""));
+    }
+
+    #[test]
+    fn failed_changes_are_reported_and_undone() {
+        let backend = FakeBackend {
+            fail: true,
+            ..FakeBackend::with_devices(&[(DeviceKind::Output, "speakers", 40, true)])
+        };
+        let (devices_sender, devices) = mpsc::channel();
+        let watcher = watch_audio(Box::new(backend), move |devices| {
+            let _ = devices_sender.send(devices);
+        });
+        let timeout = Duration::from_secs(5);
+        let device = devices.recv_timeout(timeout).unwrap().outputs[0].clone();
+
+        let (error_sender, errors) = mpsc::channel();
+        watcher.change(
+            move |backend| change_volume(backend, DeviceKind::Output, &device, 10),
+            move |e| {
+                let _ = error_sender.send(e);
+            },
+        );
+        assert!(errors.recv_timeout(timeout).is_ok());
+        // Reported again although nothing changed, so the UI drops the volume it guessed
+        let devices = devices.recv_timeout(timeout).unwrap();
+        assert_eq!(
+            devices.default_device(DeviceKind::Output).unwrap().volume,
+            40
+        );
+    }
+}
@@ -0,0 +1,894 @@
+use crate::audio::{AudioDevice, volume_icon};
+use crate::brightness::Backlight;
+use crate::calendar::{Event, date_from_glib};
+use crate::config::{BarConfig, BarPosition, CustomModuleConfig, FullscreenBehavior, TimerConfig};
+use crate::connectivity::ConnectivityStatus;
+use crate::custom::{CustomModule, CustomOutput};
+use crate::messages::{AppMessage, BarMessage};
+use crate::mpris::Player;
+use crate::power_profiles::{PowerProfiles, profile_icon, profile_label};
+use crate::timer::Timer;
+use crate::window_list::{Workspaces, update_window_list};
+use chrono::Local;
+use gtk::prelude::*;
+use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use relm4::adw::glib;
+use relm4::adw::prelude::AnimationExt;
+use relm4::gtk::Image;
+use relm4::prelude::*;
+
+/// What every bar shows. The app owns the data sources and sends each bar a copy after
+/// every change.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BarState {
+    pub visible: bool,
+    pub clock: String,
+    pub clock_tooltip: String,
+    /// "City time" for each extra clock next to the main one
+    pub bar_clocks: Vec<String>,
+    pub date: String,
+    pub week: String,
+    /// City and time for each time zone in the calendar popover
+    pub zone_clocks: Vec<(String, String)>,
+    /// Day headings with their events, empty without calendars
+    pub events: Vec<(String, Vec<Event>)>,
+    pub next_event: Option<Event>,
+    /// e.g. "Standup in 8 min", kept here so the bars update as the minutes pass
+    pub next_event_text: String,
+    pub timer: Option<Timer>,
+    /// The time the timer shows, which changes every second while it runs
+    pub timer_text: String,
+    pub workspaces: Workspaces,
+    pub custom_outputs: Vec<CustomOutput>,
+    pub system_info: (String, String),
+    pub wifi_status: ConnectivityStatus,
+    pub bluetooth_status: ConnectivityStatus,
+    pub vpn_active: bool,
+    pub volume: Option<AudioDevice>,
+    pub brightness: Option<Backlight>,
+    pub power_profiles: Option<PowerProfiles>,
+    pub media: Option<Player>,
+    pub notifications_enabled: bool,
+    pub unread_notifications: usize,
+    pub dnd: bool,
+    /// The niri overview is open, auto-hiding bars show meanwhile
+    pub overview_open: bool,
+    /// Auto-hiding bars stay revealed, set from `adwbar show`
+    pub reveal_pinned: bool,
+}
+
+/// Pixels of an auto-hidden bar left on screen for the pointer to touch
+const HIDDEN_PEEK: i32 = 2;
+/// Length of the slide in and out in milliseconds
+const SLIDE_DURATION: u32 = 200;
+
+pub struct BarInit {
+    /// The compositor picks the output when None
+    pub monitor: Option<gtk::gdk::Monitor>,
+    pub state: BarState,
+    pub config: BarConfig,
+    pub custom_modules: Vec<CustomModuleConfig>,
+    pub timer_config: TimerConfig,
+}
+
+/// One bar window; clicks are passed on to the app as its messages
+pub struct Bar {
+    state: BarState,
+    config: BarConfig,
+    /// Vertical for bars on the left or right
+    orientation: gtk::Orientation,
+    /// Popovers open away from the screen edge
+    popover_direction: gtk::ArrowType,
+    window_list_container: gtk::Box,
+    bar_clocks: Vec<gtk::Label>,
+    zone_clocks: Vec<gtk::Label>,
+    events_box: gtk::Box,
+    custom_modules: Vec<CustomModule>,
+    timer_config: TimerConfig,
+    window: adw::ApplicationWindow,
+    /// The screen edge the bar is anchored to
+    edge: Edge,
+    /// Moves the bar's margin on `edge` when auto-hiding
+    slide: adw::TimedAnimation,
+    revealed: bool,
+    hovered: bool,
+    /// A popover of the bar is open, which takes the pointer away from the bar
+    menu_open: bool,
+    /// Pending hide after the pointer left
+    hide_timeout: Option<glib::SourceId>,
+    /// A window is fullscreen on the bar's output
+    fullscreen: bool,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Bar {
+    type Input = BarMessage;
+    type Output = AppMessage;
+    type Init = BarInit;
+
+    view! {
+        window = adw::ApplicationWindow {
+            set_title: Some("Layer Shell with Adwaita"),
+            set_default_size: (24, 24),
+            add_css_class: "main-bar-window",
+            #[watch]
+            set_visible: model.state.visible,
+            add_controller = gtk::EventControllerMotion {
+                connect_enter[sender] => move |_, _, _| {
+                    sender.input(BarMessage::PointerEntered);
+                },
+                connect_leave[sender] => move |_| {
+                    sender.input(BarMessage::PointerLeft);
+                },
+            },
+
+            gtk::CenterBox {
+                add_css_class: "main-container",
+                set_orientation: model.orientation,
+
+                // window list container
+                set_start_widget: Some(&window_list_container),
+
+                // clock and timer
+                #[wrap(Some)]
+                set_center_widget = &gtk::Box {
+                    add_css_class: "bar-section",
+                    set_orientation: model.orientation,
+                    set_spacing: 2,
+
+                    // clock, opens the calendar
+                    gtk::MenuButton {
+                        add_css_class: "clock-container",
+                        add_css_class: "flat",
+                        set_direction: model.popover_direction,
+                        #[watch]
+                        set_tooltip_text: Some(&model.state.clock_tooltip),
+                        connect_active_notify[sender] => move |button| {
+                            sender.input(BarMessage::MenuToggled(button.is_active()));
+                        },
+                        // Right click switches to the alternate format
+                        add_controller = gtk::GestureClick {
+                            set_button: 3,
+                            connect_pressed[sender] => move |_, _, _, _| {
+                                sender.output(AppMessage::ClockToggleFormat).ok();
+                            },
+                        },
+                        #[wrap(Some)]
+                        set_child = &gtk::Box {
+                            set_orientation: model.orientation,
+                            set_spacing: 6,
+                            gtk::Label {
+                                #[watch]
+                                set_label: &model.stacked(&model.state.clock),
+                                set_justify: gtk::Justification::Center,
+                                add_css_class: "title-2",
+                            },
+                            #[local_ref]
+                            bar_clocks_box -> gtk::Box {
+                                set_orientation: model.orientation,
+                                set_spacing: 6,
+                                set_visible: !model.bar_clocks.is_empty(),
+                            },
+                            // Upcoming meeting
+                            gtk::Label {
+                                #[watch]
+                                set_visible: model.state.next_event.is_some() && !model.is_vertical(),
+                                #[watch]
+                                set_label: &model.state.next_event_text,
+                                add_css_class: "next-event",
+                            },
+                        },
+                        #[wrap(Some)]
+                        set_popover = &gtk::Popover {
+                            add_css_class: "calendar-popover",
+                            connect_show[calendar] => move |_| {
+                                // Always open on today
+                                if let Ok(today) = glib::DateTime::now_local() {
+                                    calendar.select_day(&today);
+                                }
+                            },
+                            gtk::Box {
+                                set_orientation: gtk::Orientation::Vertical,
+                                set_spacing: 6,
+                                gtk::Label {
+                                    #[watch]
+                                    set_label: &model.state.date,
+                                    add_css_class: "title-4",
+                                },
+                                gtk::Label {
+                                    #[watch]
+                                    set_label: &model.state.week,
+                                    add_css_class: "dim-label",
+                                },
+                                #[name = "calendar"]
+                                gtk::Calendar {
+                                    connect_day_selected[sender] => move |calendar| {
+                                        if let Some(date) = date_from_glib(&calendar.date()) {
+                                            sender.output(AppMessage::CalendarDaySelected(date)).ok();
+                                        }
+                                    },
+                                },
+                                #[local_ref]
+                                events_box -> gtk::Box {
+                                    set_orientation: gtk::Orientation::Vertical,
+                                    set_spacing: 4,
+                                    #[watch]
+                                    set_visible: !model.state.events.is_empty(),
+                                },
+                                #[local_ref]
+                                zones_box -> gtk::Box {
+                                    set_orientation: gtk::Orientation::Vertical,
+                                    set_spacing: 4,
+                                    set_margin_top: 6,
+                                    set_visible: !model.zone_clocks.is_empty(),
+                                },
+                            },
+                        },
+                    },
+
+                    // Timer, shows the remaining time while one runs
+                    gtk::MenuButton {
+                        add_css_class: "timer-container",
+                        add_css_class: "flat",
+                        set_direction: model.popover_direction,
+                        connect_active_notify[sender] => move |button| {
+                            sender.input(BarMessage::MenuToggled(button.is_active()));
+                        },
+                        #[wrap(Some)]
+                        set_child = &gtk::Box {
+                            set_orientation: model.orientation,
+                            set_spacing: 4,
+                            Image {
+                                #[watch]
+                                set_icon_name: Some(if model.state.timer.as_ref().is_some_and(|timer| !timer.is_running()) {
+                                    "media-playback-pause-symbolic"
+                                } else {
+                                    "alarm-symbolic"
+                                }),
+                                set_pixel_size: 16,
+                            },
+                            gtk::Label {
+                                #[watch]
+                                set_visible: model.state.timer.is_some(),
+                                #[watch]
+                                set_label: &model.stacked(&model.state.timer_text),
+                                set_justify: gtk::Justification::Center,
+                                add_css_class: "system-info-label",
+                                add_css_class: "numeric",
+                            },
+                        },
+                        #[wrap(Some)]
+                        set_popover = &gtk::Popover {
+                            add_css_class: "timer-popover",
+                            gtk::Box {
+                                set_orientation: gtk::Orientation::Vertical,
+                                set_spacing: 6,
+
+                                // Running timer
+                                gtk::Box {
+                                    set_orientation: gtk::Orientation::Vertical,
+                                    set_spacing: 6,
+                                    #[watch]
+                                    set_visible: model.state.timer.is_some(),
+                                    gtk::Label {
+                                        #[watch]
+                                        set_label: &model.state.timer_text,
+                                        add_css_class: "title-1",
+                                        add_css_class: "numeric",
+                                    },
+                                    gtk::Label {
+                                        #[watch]
+                                        set_label: &model.state.timer.as_ref().map(Timer::description).unwrap_or_default(),
+                                        add_css_class: "dim-label",
+                                    },
+                                    gtk::Box {
+                                        set_spacing: 6,
+                                        set_homogeneous: true,
+                                        gtk::Button {
+                                            #[watch]
+                                            set_label: if model.state.timer.as_ref().is_some_and(Timer::is_running) {
+                                                "Pause"
+                                            } else {
+                                                "Resume"
+                                            },
+                                            connect_clicked[sender] => move |_| {
+                                                sender.output(AppMessage::TimerPauseResume).ok();
+                                            },
+                                        },
+                                        gtk::Button {
+                                            set_label: "Stop",
+                                            add_css_class: "destructive-action",
+                                            connect_clicked[sender] => move |_| {
+                                                sender.output(AppMessage::TimerStop).ok();
+                                            },
+                                        },
+                                    },
+                                },
+
+                                // Starting a new one
+                                gtk::Box {
+                                    set_orientation: gtk::Orientation::Vertical,
+                                    set_spacing: 6,
+                                    #[watch]
+                                    set_visible: model.state.timer.is_none(),
+                                    gtk::Box {
+                                        set_spacing: 6,
+                                        #[name = "timer_minutes"]
+                                        gtk::SpinButton::with_range(1.0, 240.0, 1.0) {
+                                            set_value: model.timer_config.default_minutes as f64,
+                                        },
+                                        gtk::Label {
+                                            set_label: "min",
+                                        },
+                                        gtk::Button {
+                                            set_label: "Start",
+                                            set_hexpand: true,
+                                            add_css_class: "suggested-action",
+                                            connect_clicked[sender, timer_minutes] => move |_| {
+                                                sender.output(AppMessage::StartCountdown(timer_minutes.value() as u32)).ok();
+                                            },
+                                        },
+                                    },
+                                    gtk::Button {
+                                        set_label: "Pomodoro",
+                                        #[watch]
+                                        set_tooltip_text: Some(&format!(
+                                            "{} min focus, {} min breaks",
+                                            model.timer_config.work_minutes,
+                                            model.timer_config.break_minutes,
+                                        )),
+                                        connect_clicked[sender] => move |_| {
+                                            sender.output(AppMessage::StartPomodoro).ok();
+                                        },
+                                    },
+                                    gtk::Button {
+                                        set_label: "Stopwatch",
+                                        connect_clicked[sender] => move |_| {
+                                            sender.output(AppMessage::StartStopwatch).ok();
+                                        },
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+
+                // System info container
+                #[wrap(Some)]
+                set_end_widget = &gtk::Box {
+                    add_css_class: "system-info-container",
+                    add_css_class: "bar-section",
+                    set_orientation: model.orientation,
+                    set_spacing: 5,
+                    // Custom script modules
+                    #[local_ref]
+                    custom_box -> gtk::Box {
+                        set_orientation: model.orientation,
+                        set_spacing: 5,
+                    },
+                    // Now playing
+                    gtk::Button {
+                        add_css_class: "media-button",
+                        add_css_class: "flat",
+                        #[watch]
+                        set_visible: model.state.media.is_some(),
+                        #[watch]
+                        set_tooltip_text: model.state.media.as_ref().map(|player| player.identity.as_str()),
+                        connect_clicked[sender] => move |_| {
+                            sender.output(AppMessage::MediaPlayPause).ok();
+                        },
+                        add_controller = gtk::EventControllerScroll::new(
+                            gtk::EventControllerScrollFlags::VERTICAL,
+                        ) {
+                            connect_scroll[sender] => move |_, _, dy| {
+                                sender.output(AppMessage::MediaScroll(dy)).ok();
+                                glib::Propagation::Stop
+                            },
+                        },
+                        gtk::Box {
+                            set_orientation: model.orientation,
+                            set_spacing: 4,
+                            Image {
+                                #[watch]
+                                set_icon_name: Some(if model.state.media.as_ref().is_some_and(Player::is_playing) {
+                                    "media-playback-pause-symbolic"
+                                } else {
+                                    "media-playback-start-symbolic"
+                                }),
+                                set_pixel_size: 16,
+                            },
+                            gtk::Label {
+                                #[watch]
+                                set_visible: !model.is_vertical(),
+                                #[watch]
+                                set_label: &model.state.media.as_ref().map(Player::now_playing).unwrap_or_default(),
+                                set_max_width_chars: 40,
+                                set_ellipsize: gtk::pango::EllipsizeMode::End,
+                                add_css_class: "system-info-label",
+                            },
+                        }
+                    },
+                    // Brightness
+                    gtk::Box {
+                        add_css_class: "brightness-container",
+                        set_orientation: model.orientation,
+                        set_spacing: 4,
+                        #[watch]
+                        set_visible: model.state.brightness.is_some(),
+                        add_controller = gtk::EventControllerScroll::new(
+                            gtk::EventControllerScrollFlags::VERTICAL,
+                        ) {
+                            connect_scroll[sender] => move |_, _, dy| {
+                                sender.output(AppMessage::BrightnessScroll(dy)).ok();
+                                glib::Propagation::Stop
+                            },
+                        },
+                        Image {
+                            set_icon_name: Some("display-brightness-symbolic"),
+                            set_pixel_size: 16,
+                        },
+                        gtk::Label {
+                            #[watch]
+                            set_label: &model
+                                .state
+                                .brightness
+                                .as_ref()
+                                .map(|backlight| format!("{}%", backlight.percent()))
+                                .unwrap_or_default(),
+                            add_css_class: "system-info-label",
+                        },
+                    },
+                    // Volume
+                    gtk::Button {
+                        add_css_class: "volume-button",
+                        add_css_class: "flat",
+                        connect_clicked[sender] => move |_| {
+                            sender.output(AppMessage::VolumeMuteToggle).ok();
+                        },
+                        add_controller = gtk::EventControllerScroll::new(
+                            gtk::EventControllerScrollFlags::VERTICAL,
+                        ) {
+                            connect_scroll[sender] => move |_, _, dy| {
+                                sender.output(AppMessage::VolumeScroll(dy)).ok();
+                                glib::Propagation::Stop
+                            },
+                        },
+                        gtk::Box {
+                            set_orientation: model.orientation,
+                            set_spacing: 4,
+                            Image {
+                                #[watch]
+                                set_icon_name: Some(volume_icon(model.state.volume.as_ref())),
+                                set_pixel_size: 16,
+                            },
+                            gtk::Label {
+                                #[watch]
+                                set_label: &model
+                                    .state
+                                    .volume
+                                    .as_ref()
+                                    .map(|device| format!("{}%", device.volume))
+                                    .unwrap_or_default(),
+                                add_css_class: "system-info-label",
+                            },
+                        }
+                    },
+                    // Notifications, click toggles Do Not Disturb
+                    gtk::Button {
+                        add_css_class: "notifications-button",
+                        add_css_class: "flat",
+                        #[watch]
+                        set_visible: model.state.notifications_enabled,
+                        #[watch]
+                        set_tooltip_text: Some(if model.state.dnd {
+                            "Do Not Disturb is on"
+                        } else {
+                            "Do Not Disturb is off"
+                        }),
+                        connect_clicked[sender] => move |_| {
+                            sender.output(AppMessage::ToggleDoNotDisturb).ok();
+                        },
+                        gtk::Box {
+                            set_orientation: model.orientation,
+                            set_spacing: 2,
+                            Image {
+                                #[watch]
+                                set_icon_name: Some(if model.state.dnd {
+                                    "notifications-disabled-symbolic"
+                                } else {
+                                    "preferences-system-notifications-symbolic"
+                                }),
+                                set_pixel_size: 16,
+                            },
+                            // Unread count
+                            gtk::Label {
+                                #[watch]
+                                set_visible: model.state.unread_notifications > 0,
+                                #[watch]
+                                set_label: &model.state.unread_notifications.to_string(),
+                                add_css_class: "system-info-label",
+                            },
+                        }
+                    },
+                    gtk::Button {
+                        add_css_class: "system-info-button",
+                        add_css_class: "flat",
+                        connect_clicked[sender] => move |_| {
+                            sender.output(AppMessage::SystemInfoClicked).ok();
+                        },
+                        gtk::Box {
+                            set_orientation: model.orientation,
+                            set_spacing: 8,
+                            // System info
+                            gtk::Label {
+                                #[watch]
+                                set_label: &if model.is_vertical() {
+                                    format!("{}\n{}", model.state.system_info.0, model.state.system_info.1)
+                                } else {
+                                    format!("CPU: {} GPU: {}", model.state.system_info.0, model.state.system_info.1)
+                                },
+                                set_justify: gtk::Justification::Center,
+                                add_css_class: "system-info-label",
+                            },
+                            // WiFi icon
+                            Image {
+                                #[watch]
+                                set_icon_name: Some(if model.state.wifi_status.enabled {
+                                    "network-wireless-signal-excellent-symbolic"
+                                } else {
+                                    "network-wireless-disabled-symbolic"
+                                }),
+                                set_pixel_size: 16,
+                            },
+                            // Bluetooth icon
+                            Image {
+                                #[watch]
+                                set_icon_name: Some(if model.state.bluetooth_status.enabled {
+                                    "bluetooth-active-symbolic"
+                                } else {
+                                    "bluetooth-disabled-symbolic"
+                                }),
+                                set_pixel_size: 16,
+                            },
+                            // Power profile icon, only shown when not balanced
+                            Image {
+                                #[watch]
+                                set_visible: model
+                                    .state
+                                    .power_profiles
+                                    .as_ref()
+                                    .is_some_and(|profiles| profiles.active != "balanced"),
+                                #[watch]
+                                set_icon_name: model
+                                    .state
+                                    .power_profiles
+                                    .as_ref()
+                                    .map(|profiles| profile_icon(&profiles.active)),
+                                #[watch]
+                                set_tooltip_text: model
+                                    .state
+                                    .power_profiles
+                                    .as_ref()
+                                    .map(|profiles| profile_label(&profiles.active)),
+                                set_pixel_size: 16,
+                            },
+                            // VPN icon
+                            Image {
+                                #[watch]
+                                set_visible: model.state.vpn_active,
+                                set_icon_name: Some("network-vpn-symbolic"),
+                                set_pixel_size: 16,
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn init(
+        init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let config = init.config;
+        let position = config.position;
+        let (orientation, popover_direction) = match position {
+            BarPosition::Top => (gtk::Orientation::Horizontal, gtk::ArrowType::Down),
+            BarPosition::Bottom => (gtk::Orientation::Horizontal, gtk::ArrowType::Up),
+            BarPosition::Left => (gtk::Orientation::Vertical, gtk::ArrowType::Right),
+            BarPosition::Right => (gtk::Orientation::Vertical, gtk::ArrowType::Left),
+        };
+
+        // Initialize layer shell, anchored to its edge and both neighbouring ones
+        root.init_layer_shell();
+        root.set_layer(Layer::Top);
+        root.auto_exclusive_zone_enable();
+        let anchors @ [edge, ..] = match position {
+            BarPosition::Top => [Edge::Top, Edge::Left, Edge::Right],
+            BarPosition::Bottom => [Edge::Bottom, Edge::Left, Edge::Right],
+            BarPosition::Left => [Edge::Left, Edge::Top, Edge::Bottom],
+            BarPosition::Right => [Edge::Right, Edge::Top, Edge::Bottom],
+        };
+        for edge in anchors {
+            root.set_anchor(edge, true);
+            root.set_margin(edge, config.margin);
+        }
+        if position.is_vertical() {
+            root.set_width_request(config.thickness);
+            root.add_css_class("vertical");
+        } else {
+            root.set_height_request(config.thickness);
+        }
+        root.add_css_class(position.name());
+        root.set_monitor(init.monitor.as_ref());
+
+        let window_list_container = gtk::Box::new(orientation, 5);
+        window_list_container.add_css_class("bar-section");
+
+        let bar_clocks_box = &gtk::Box::new(orientation, 6);
+        let bar_clocks: Vec<gtk::Label> = init
+            .state
+            .bar_clocks
+            .iter()
+            .map(|_| {
+                let label = gtk::Label::new(None);
+                label.add_css_class("zone-clock");
+                bar_clocks_box.append(&label);
+                label
+            })
+            .collect();
+
+        // One row per extra time zone, the times are filled in on every update
+        let zones_box = &gtk::Box::new(gtk::Orientation::Vertical, 4);
+        let zone_clocks: Vec<gtk::Label> = init
+            .state
+            .zone_clocks
+            .iter()
+            .map(|(city, _)| {
+                let row = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+                let city = gtk::Label::new(Some(city));
+                city.set_hexpand(true);
+                city.set_xalign(0.0);
+                let time = gtk::Label::new(None);
+                time.add_css_class("numeric");
+                row.append(&city);
+                row.append(&time);
+                zones_box.append(&row);
+                time
+            })
+            .collect();
+
+        let events_box = &gtk::Box::new(gtk::Orientation::Vertical, 4);
+        let custom_box = &gtk::Box::new(orientation, 5);
+        let custom_modules: Vec<CustomModule> = init
+            .custom_modules
+            .into_iter()
+            .map(|config| {
+                let module = CustomModule::new(config);
+                custom_box.append(&module.button);
+                module
+            })
+            .collect();
+
+        let window = root.clone();
+        let slide = adw::TimedAnimation::new(
+            &root,
+            config.margin as f64,
+            config.margin as f64,
+            SLIDE_DURATION,
+            adw::CallbackAnimationTarget::new(move |value| {
+                window.set_margin(edge, value.round() as i32);
+            }),
+        );
+        slide.set_easing(adw::Easing::EaseOutCubic);
+
+        let mut model = Bar {
+            state: BarState::default(),
+            config,
+            orientation,
+            popover_direction,
+            window_list_container: window_list_container.clone(),
+            bar_clocks,
+            zone_clocks,
+            events_box: events_box.clone(),
+            custom_modules,
+            timer_config: init.timer_config,
+            window: root.clone(),
+            edge,
+            slide,
+            revealed: true,
+            hovered: false,
+            menu_open: false,
+            hide_timeout: None,
+            fullscreen: false,
+        };
+
+        model.update_state(init.state);
+        // Auto-hiding bars show briefly on start so it is clear where they went
+        model.update_reveal(&sender);
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            BarMessage::Update(state) => self.update_state(*state),
+            BarMessage::PointerEntered => self.hovered = true,
+            BarMessage::PointerLeft => self.hovered = false,
+            BarMessage::MenuToggled(open) => self.menu_open = open,
+            BarMessage::Fullscreen(fullscreen) => {
+                if fullscreen == self.fullscreen {
+                    return;
+                }
+                self.fullscreen = fullscreen;
+                self.apply_fullscreen();
+            }
+            BarMessage::HideTimeout => {
+                self.hide_timeout = None;
+                if !self.wants_reveal() {
+                    self.slide_to(false);
+                }
+                return;
+            }
+        }
+        self.update_reveal(&sender);
+    }
+}
+
+impl Bar {
+    fn is_vertical(&self) -> bool {
+        self.config.position.is_vertical()
+    }
+
+    /// Moves the bar out of the way of a fullscreen window. Windows are told apart from
+    /// maximized ones only by their size, so this trusts it only while the bar holds its
+    /// exclusive zone, and hiding keeps the zone so maximized windows don't grow.
+    fn apply_fullscreen(&self) {
+        let covered = self.fullscreen && self.revealed;
+        let (layer, opacity) = match self.config.fullscreen {
+            FullscreenBehavior::Stay => return,
+            FullscreenBehavior::Bottom if covered => (Layer::Bottom, 1.0),
+            FullscreenBehavior::Hide if covered => (Layer::Background, 0.0),
+            _ => (Layer::Top, 1.0),
+        };
+        self.window.set_layer(layer);
+        self.window.set_opacity(opacity);
+    }
+
+    /// Whether an auto-hiding bar should be on screen right now
+    fn wants_reveal(&self) -> bool {
+        !self.config.autohide
+            || self.hovered
+            || self.menu_open
+            || self.state.overview_open
+            || self.state.reveal_pinned
+    }
+
+    /// Slides the bar in right away, or out once `autohide_delay` passed without a reason
+    /// to stay
+    fn update_reveal(&mut self, sender: &ComponentSender<Self>) {
+        if self.wants_reveal() {
+            if let Some(timeout) = self.hide_timeout.take() {
+                crate::remove_pending(&timeout);
+            }
+            if !self.revealed {
+                self.slide_to(true);
+            }
+        } else if self.revealed && self.hide_timeout.is_none() {
+            let sender = sender.clone();
+            self.hide_timeout = Some(glib::timeout_add_local_once(
+                std::time::Duration::from_millis(self.config.autohide_delay.into()),
+                move || sender.input(BarMessage::HideTimeout),
+            ));
+        }
+    }
+
+    /// Animates the margin on the bar's edge so all but a sliver of it leaves the screen,
+    /// giving windows its space while hidden
+    fn slide_to(&mut self, revealed: bool) {
+        self.revealed = revealed;
+        self.apply_fullscreen();
+        let target = if revealed {
+            self.window.auto_exclusive_zone_enable();
+            self.config.margin
+        } else {
+            self.window.set_exclusive_zone(0);
+            // `thickness` is only the smallest size, the content may need more
+            let size = if self.is_vertical() {
+                self.window.width()
+            } else {
+                self.window.height()
+            };
+            HIDDEN_PEEK - size.max(self.config.thickness)
+        };
+        // Start from wherever a running slide got to
+        self.slide.pause();
+        self.slide
+            .set_value_from(self.window.margin(self.edge) as f64);
+        self.slide.set_value_to(target as f64);
+        self.slide.play();
+    }
+
+    /// Puts each part of a time like "12:30" on its own line in vertical bars
+    fn stacked(&self, text: &str) -> String {
+        if self.is_vertical() {
+            text.replace([':', ' '], "\n")
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Takes the new state, rebuilding the widgets made by hand only when their part changed
+    fn update_state(&mut self, state: BarState) {
+        for (label, text) in self.bar_clocks.iter().zip(&state.bar_clocks) {
+            label.set_label(&self.stacked(text));
+        }
+        for (label, (_, time)) in self.zone_clocks.iter().zip(&state.zone_clocks) {
+            label.set_label(time);
+        }
+        if state.workspaces != self.state.workspaces {
+            update_window_list(&self.window_list_container, &state.workspaces);
+        }
+        if state.events != self.state.events {
+            self.rebuild_events(&state.events);
+        }
+        for (module, output) in self.custom_modules.iter_mut().zip(&state.custom_outputs) {
+            module.update(output);
+        }
+        self.state = state;
+    }
+
+    /// Lists the events of each day under its heading
+    fn rebuild_events(&self, days: &[(String, Vec<Event>)]) {
+        while let Some(child) = self.events_box.first_child() {
+            self.events_box.remove(&child);
+        }
+        for (title, events) in days {
+            let header = gtk::Label::new(Some(title));
+            header.add_css_class("heading");
+            header.set_xalign(0.0);
+            header.set_margin_top(6);
+            self.events_box.append(&header);
+
+            if events.is_empty() {
+                let empty = gtk::Label::new(Some("No events"));
+                empty.add_css_class("dim-label");
+                empty.set_xalign(0.0);
+                self.events_box.append(&empty);
+            }
+            for event in events {
+                self.events_box.append(&event_row(event));
+            }
+        }
+    }
+}
+
+fn event_row(event: &Event) -> gtk::Box {
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    let time = gtk::Label::new(Some(&event.time_range()));
+    time.add_css_class("dim-label");
+    time.add_css_class("numeric");
+    row.append(&time);
+
+    let summary = gtk::Label::new(Some(&event.summary));
+    summary.set_xalign(0.0);
+    summary.set_hexpand(true);
+    summary.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    summary.set_max_width_chars(30);
+    summary.set_tooltip_text(event.location.as_deref());
+    row.append(&summary);
+    row
+}
+
+/// e.g. "Standup in 8 min"
+pub fn next_event_label(event: &Event) -> String {
+    let minutes = (event.start - Local::now()).num_minutes().max(0);
+    if minutes == 0 {
+        format!("{} now", event.summary)
+    } else {
+        format!("{} in {} min", event.summary, minutes)
+    }
+}
@@ -0,0 +1,198 @@
+use crate::ipc::{Reply, Request, Status, reload};
+use crate::messages::AppMessage;
+use std::collections::HashMap;
+use std::time::Duration;
+use zbus::blocking::Connection;
+use zbus::fdo;
+use zbus::zvariant::Value;
+
+const NAME: &str = "me.bofusland.adwbar.Bar";
+const PATH: &str = "/me/bofusland/adwbar/Bar";
+const INTERFACE: &str = "me.bofusland.adwbar.Bar";
+
+/// The same commands as the IPC socket, for busctl and gdbus
+struct BarInterface {
+    sender: relm4::Sender<AppMessage>,
+}
+
+impl BarInterface {
+    async fn request(&self, request: Request) -> fdo::Result<Reply> {
+        let (reply, response) = relm4::channel();
+        self.sender.emit(AppMessage::IpcRequest(request, reply));
+        match response.recv().await {
+            Some(Reply::Error(e)) => Err(fdo::Error::Failed(e)),
+            Some(reply) => Ok(reply),
+            None => Err(fdo::Error::Failed("The bar did not answer".to_string())),
+        }
+    }
+
+    async fn status(&self) -> fdo::Result<Status> {
+        match self.request(Request::Status).await? {
+            Reply::Status(status) => Ok(status),
+            _ => Err(fdo::Error::Failed("Unexpected reply".to_string())),
+        }
+    }
+}
+
+#[zbus::interface(name = "me.bofusland.adwbar.Bar")]
+impl BarInterface {
+    async fn show(&self) -> fdo::Result<()> {
+        self.request(Request::Show).await.map(|_| ())
+    }
+
+    async fn hide(&self) -> fdo::Result<()> {
+        self.request(Request::Hide).await.map(|_| ())
+    }
+
+    async fn toggle(&self) -> fdo::Result<()> {
+        self.request(Request::Toggle).await.map(|_| ())
+    }
+
+    async fn show_control_center(&self) -> fdo::Result<()> {
+        self.request(Request::ShowControlCenter).await.map(|_| ())
+    }
+
+    async fn hide_control_center(&self) -> fdo::Result<()> {
+        self.request(Request::HideControlCenter).await.map(|_| ())
+    }
+
+    async fn toggle_control_center(&self) -> fdo::Result<()> {
+        self.request(Request::ToggleControlCenter).await.map(|_| ())
+    }
+
+    async fn set_module_text(&self, id: String, text: String) -> fdo::Result<()> {
+        self.request(Request::SetModuleText { id, text })
+            .await
+            .map(|_| ())
+    }
+
+    /// Restarts the bar shortly after replying, so the caller gets its answer
+    fn reload(&self) {
+        std::thread::spawn(|| {
+            std::thread::sleep(Duration::from_millis(100));
+            reload();
+        });
+    }
+
+    #[zbus(property)]
+    async fn visible(&self) -> fdo::Result<bool> {
+        Ok(self.status().await?.visible)
+    }
+
+    #[zbus(property)]
+    async fn control_center_visible(&self) -> fdo::Result<bool> {
+        Ok(self.status().await?.control_center_visible)
+    }
+
+    #[zbus(property)]
+    async fn clock(&self) -> fdo::Result<String> {
+        Ok(self.status().await?.clock)
+    }
+
+    #[zbus(property)]
+    async fn do_not_disturb(&self) -> fdo::Result<bool> {
+        Ok(self.status().await?.do_not_disturb)
+    }
+
+    #[zbus(property)]
+    async fn unread_notifications(&self) -> fdo::Result<u32> {
+        Ok(self.status().await?.unread_notifications as u32)
+    }
+
+    /// Empty when no timer is running
+    #[zbus(property)]
+    async fn timer(&self) -> fdo::Result<String> {
+        Ok(self.status().await?.timer.unwrap_or_default())
+    }
+
+    /// Empty when no player is around
+    #[zbus(property)]
+    async fn media(&self) -> fdo::Result<String> {
+        Ok(self.status().await?.media.unwrap_or_default())
+    }
+
+    /// Custom module ids and their text
+    #[zbus(property)]
+    async fn modules(&self) -> fdo::Result<HashMap<String, String>> {
+        Ok(modules(&self.status().await?))
+    }
+}
+
+fn modules(status: &Status) -> HashMap<String, String> {
+    status
+        .modules
+        .iter()
+        .map(|module| (module.id.clone(), module.text.clone()))
+        .collect()
+}
+
+/// Owns me.bofusland.adwbar.Bar on the session bus while adwbar runs
+pub struct BarService {
+    conn: Connection,
+    /// What listeners were last told about
+    last_status: Option<Status>,
+}
+
+impl BarService {
+    pub fn start(sender: relm4::Sender<AppMessage>) -> zbus::Result<Self> {
+        let conn = zbus::blocking::connection::Builder::session()?
+            .serve_at(PATH, BarInterface { sender })?
+            .name(NAME)?
+            .build()?;
+        Ok(Self {
+            conn,
+            last_status: None,
+        })
+    }
+
+    /// Sends PropertiesChanged with the new value of every property that differs from
+    /// the last status
+    pub fn update(&mut self, status: Status) {
+        if self.last_status.as_ref() == Some(&status) {
+            return;
+        }
+        let Some(last) = self.last_status.replace(status.clone()) else {
+            return;
+        };
+
+        let mut changed: HashMap<&str, Value> = HashMap::new();
+        if last.visible != status.visible {
+            changed.insert("Visible", status.visible.into());
+        }
+        if last.control_center_visible != status.control_center_visible {
+            changed.insert("ControlCenterVisible", status.control_center_visible.into());
+        }
+        if last.clock != status.clock {
+            changed.insert("Clock", status.clock.clone().into());
+        }
+        if last.do_not_disturb != status.do_not_disturb {
+            changed.insert("DoNotDisturb", status.do_not_disturb.into());
+        }
+        if last.unread_notifications != status.unread_notifications {
+            changed.insert(
+                "UnreadNotifications",
+                (status.unread_notifications as u32).into(),
+            );
+        }
+        if last.timer != status.timer {
+            changed.insert("Timer", status.timer.clone().unwrap_or_default().into());
+        }
+        if last.media != status.media {
+            changed.insert("Media", status.media.clone().unwrap_or_default().into());
+        }
+        if last.modules != status.modules {
+            changed.insert("Modules", modules(&status).into());
+        }
+
+        let invalidated: &[&str] = &[];
+        if let Err(e) = self.conn.emit_signal(
+            None::<&str>,
+            PATH,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+            &(INTERFACE, changed, invalidated),
+        ) {
+            eprintln!("Failed to announce bar changes on D-Bus: {}", e);
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use crate::messages::ConfMessage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use zbus::blocking::Connection;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+const AGENT_PATH: &str = "/me/bofusland/adwbar/agent";
+
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, HashMap<String, OwnedValue>>>;
+
+/// Adapter picked in the control center, used instead of the first one while it exists
+static SELECTED_ADAPTER: Mutex<Option<String>> = Mutex::new(None);
+
+/// A Bluetooth controller exposed by BlueZ, e.g. hci0 or a USB dongle on hci1
+#[derive(Debug, Clone, PartialEq)]
+pub struct BluetoothAdapter {
+    pub path: String,
+    pub name: String,
+    pub powered: bool,
+}
+
+/// A Bluetooth device known to BlueZ
+#[derive(Debug, Clone, PartialEq)]
+pub struct BluetoothDevice {
+    pub path: String,
+    pub name: String,
+    pub icon: String,
+    pub paired: bool,
+    pub connected: bool,
+    pub battery: Option<u8>,
+}
+
+impl BluetoothDevice {
+    pub fn status(&self) -> String {
+        let status = if self.connected {
+            "Connected"
+        } else if self.paired {
+            "Paired"
+        } else {
+            "Not paired"
+        };
+        match self.battery {
+            Some(battery) => format!("{} · {}%", status, battery),
+            None => status.to_string(),
+        }
+    }
+}
+
+fn get_managed_objects(conn: &Connection) -> zbus::Result<ManagedObjects> {
+    let proxy =
+        zbus::blocking::Proxy::new(conn, "org.bluez", "/", "org.freedesktop.DBus.ObjectManager")?;
+    proxy.call("GetManagedObjects", &())
+}
+
+fn property<'a, T>(properties: &'a HashMap<String, OwnedValue>, name: &str) -> Option<T>
+where
+    T: TryFrom<&'a OwnedValue>,
+{
+    properties
+        .get(name)
+        .and_then(|value| T::try_from(value).ok())
+}
+
+fn adapters_from(objects: &ManagedObjects) -> Vec<BluetoothAdapter> {
+    let mut adapters: Vec<BluetoothAdapter> = objects
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            let adapter = interfaces.get("org.bluez.Adapter1")?;
+            let name = property::<&str>(adapter, "Alias")
+                .or_else(|| path.as_str().rsplit('/').next())
+                .unwrap_or_default()
+                .to_string();
+            Some(BluetoothAdapter {
+                path: path.to_string(),
+                name,
+                powered: property(adapter, "Powered").unwrap_or(false),
+            })
+        })
+        .collect();
+    adapters.sort_by(|a, b| a.path.cmp(&b.path));
+    adapters
+}
+
+/// The selected adapter among `adapters`, or the first one
+pub fn choose_adapter(adapters: &[BluetoothAdapter]) -> Option<&BluetoothAdapter> {
+    let selected = SELECTED_ADAPTER.lock().ok()?.clone();
+    adapters
+        .iter()
+        .find(|adapter| Some(&adapter.path) == selected.as_ref())
+        .or_else(|| adapters.first())
+}
+
+/// Lists all adapters BlueZ currently knows about, failing when the bus or BlueZ is down
+pub fn read_adapters() -> zbus::Result<Vec<BluetoothAdapter>> {
+    let conn = Connection::system()?;
+    Ok(adapters_from(&get_managed_objects(&conn)?))
+}
+
+/// Returns the selected adapter, falling back to the first one available
+pub fn default_adapter() -> zbus::Result<Option<BluetoothAdapter>> {
+    Ok(choose_adapter(&read_adapters()?).cloned())
+}
+
+/// Makes the given adapter the one used by the bar and control center
+pub fn select_adapter(path: &str) {
+    if let Ok(mut selected) = SELECTED_ADAPTER.lock() {
+        *selected = Some(path.to_string());
+    }
+}
+
+/// Calls `on_change` whenever an adapter is plugged in or removed, or BlueZ starts.
+/// BlueZ forgets agents when it stops, so the session's agent is registered again then.
+pub fn watch_adapters(
+    session: Option<&BluetoothSession>,
+    on_change: impl Fn() + Send + Sync + 'static,
+) {
+    let on_change = Arc::new(on_change);
+    if let Some(session) = session {
+        let conn = session.conn.clone();
+        let on_change = on_change.clone();
+        std::thread::spawn(move || {
+            let Ok(owners) = zbus::blocking::fdo::DBusProxy::new(&conn)
+                .and_then(|proxy| proxy.receive_name_owner_changed_with_args(&[(0, "org.bluez")]))
+            else {
+                return;
+            };
+            for signal in owners {
+                if signal.args().is_ok_and(|args| args.new_owner.is_some()) {
+                    if let Err(e) = register_agent(&conn) {
+                        eprintln!("Failed to register Bluetooth agent: {}", e);
+                    }
+                    on_change();
+                }
+            }
+        });
+    }
+    std::thread::spawn(move || {
+        let Ok(conn) = Connection::system() else {
+            return;
+        };
+        let Ok(proxy) = zbus::blocking::Proxy::new(
+            &conn,
+            "org.bluez",
+            "/",
+            "org.freedesktop.DBus.ObjectManager",
+        ) else {
+            return;
+        };
+        let Ok(signals) = proxy.receive_all_signals() else {
+            return;
+        };
+
+        for signal in signals {
+            let header = signal.header();
+            let body = signal.body();
+            let adapter_changed = match header.member().map(|member| member.as_str()) {
+                Some("InterfacesAdded") => body
+                    .deserialize::<(
+                        OwnedObjectPath,
+                        HashMap<String, HashMap<String, OwnedValue>>,
+                    )>()
+                    .is_ok_and(|(_, interfaces)| interfaces.contains_key("org.bluez.Adapter1")),
+                Some("InterfacesRemoved") => body
+                    .deserialize::<(OwnedObjectPath, Vec<String>)>()
+                    .is_ok_and(|(_, interfaces)| {
+                        interfaces.iter().any(|i| i == "org.bluez.Adapter1")
+                    }),
+                _ => false,
+            };
+            if adapter_changed {
+                on_change();
+            }
+        }
+    });
+}
+
+/// Lists devices on the default adapter through BlueZ's ObjectManager, connected ones first
+pub fn read_devices() -> Vec<BluetoothDevice> {
+    let Ok(objects) = Connection::system().and_then(|conn| get_managed_objects(&conn)) else {
+        return Vec::new();
+    };
+    let Some(adapter) = choose_adapter(&adapters_from(&objects)).map(|a| a.path.clone()) else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<BluetoothDevice> = objects
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            let device = interfaces.get("org.bluez.Device1")?;
+            if property::<&ObjectPath>(device, "Adapter")?.as_str() != adapter {
+                return None;
+            }
+            let name = property::<&str>(device, "Alias")
+                .or_else(|| property::<&str>(device, "Name"))?
+                .to_string();
+            let icon = property::<&str>(device, "Icon").unwrap_or("bluetooth");
+
+            Some(BluetoothDevice {
+                path: path.to_string(),
+                name,
+                icon: format!("{}-symbolic", icon),
+                paired: property(device, "Paired").unwrap_or(false),
+                connected: property(device, "Connected").unwrap_or(false),
+                battery: interfaces
+                    .get("org.bluez.Battery1")
+                    .and_then(|battery| property(battery, "Percentage")),
+            })
+        })
+        .collect();
+
+    devices.sort_by(|a, b| {
+        (!a.connected, !a.paired, &a.name).cmp(&(!b.connected, !b.paired, &b.name))
+    });
+    devices
+}
+
+/// Checks whether the default adapter is currently scanning for devices
+pub fn read_discovering() -> bool {
+    let Ok(Some(adapter)) = default_adapter() else {
+        return false;
+    };
+    Connection::system()
+        .and_then(|conn| {
+            zbus::blocking::Proxy::new(&conn, "org.bluez", adapter.path, "org.bluez.Adapter1")?
+                .get_property::<bool>("Discovering")
+        })
+        .unwrap_or(false)
+}
+
+fn device_proxy<'a>(conn: &Connection, path: &'a str) -> zbus::Result<zbus::blocking::Proxy<'a>> {
+    zbus::blocking::Proxy::new(conn, "org.bluez", path, "org.bluez.Device1")
+}
+
+/// Connects to a paired device. Blocks until BlueZ answers, so call it off the main thread.
+pub fn connect_device(path: &str) -> zbus::Result<()> {
+    let conn = Connection::system()?;
+    device_proxy(&conn, path)?.call("Connect", &())
+}
+
+/// Disconnects a device. Blocks until BlueZ answers, so call it off the main thread.
+pub fn disconnect_device(path: &str) -> zbus::Result<()> {
+    let conn = Connection::system()?;
+    device_proxy(&conn, path)?.call("Disconnect", &())
+}
+
+/// Pairs with a device, trusts it and connects to it. Passkey confirmation goes through
+/// the agent registered by [`BluetoothSession`], so this must not run on the main thread.
+pub fn pair_device(path: &str) -> zbus::Result<()> {
+    let conn = Connection::system()?;
+    let proxy = device_proxy(&conn, path)?;
+    proxy.call::<_, _, ()>("Pair", &())?;
+    proxy.set_property("Trusted", true)?;
+    proxy.call("Connect", &())
+}
+
+/// What activating a device's row does, depending on its state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeviceAction {
+    Connect,
+    Disconnect,
+    Pair,
+}
+
+impl DeviceAction {
+    pub fn for_device(device: &BluetoothDevice) -> Self {
+        if device.connected {
+            DeviceAction::Disconnect
+        } else if device.paired {
+            DeviceAction::Connect
+        } else {
+            DeviceAction::Pair
+        }
+    }
+
+    /// Worded to follow "Could not", e.g. "Could not pair with Headphones"
+    pub fn verb(&self) -> &'static str {
+        match self {
+            DeviceAction::Connect => "connect",
+            DeviceAction::Disconnect => "disconnect",
+            DeviceAction::Pair => "pair with",
+        }
+    }
+
+    /// Blocks until BlueZ answers, so call it off the main thread
+    pub fn perform(&self, path: &str) -> zbus::Result<()> {
+        match self {
+            DeviceAction::Connect => connect_device(path),
+            DeviceAction::Disconnect => disconnect_device(path),
+            DeviceAction::Pair => pair_device(path),
+        }
+    }
+}
+
+/// Owns the system bus connection used for the pairing agent and discovery.
+/// BlueZ stops discovery and unregisters the agent when this connection goes away.
+pub struct BluetoothSession {
+    conn: Connection,
+}
+
+impl BluetoothSession {
+    pub fn new(sender: relm4::Sender<ConfMessage>) -> zbus::Result<Self> {
+        let conn = zbus::blocking::connection::Builder::system()?
+            .serve_at(AGENT_PATH, Agent { sender })?
+            .build()?;
+        // BlueZ may not be running yet, watch_adapters registers the agent once it is
+        if let Err(e) = register_agent(&conn) {
+            eprintln!("Failed to register Bluetooth agent: {}", e);
+        }
+        Ok(Self { conn })
+    }
+
+    /// Starts or stops scanning for new devices on the default adapter
+    pub fn set_discovering(&self, discovering: bool) -> zbus::Result<()> {
+        let Some(adapter) = default_adapter()? else {
+            return Ok(());
+        };
+        let proxy = zbus::blocking::Proxy::new(
+            &self.conn,
+            "org.bluez",
+            adapter.path,
+            "org.bluez.Adapter1",
+        )?;
+        proxy.call(
+            if discovering {
+                "StartDiscovery"
+            } else {
+                "StopDiscovery"
+            },
+            &(),
+        )
+    }
+}
+
+fn register_agent(conn: &Connection) -> zbus::Result<()> {
+    let manager =
+        zbus::blocking::Proxy::new(conn, "org.bluez", "/org/bluez", "org.bluez.AgentManager1")?;
+    let agent_path = ObjectPath::from_static_str_unchecked(AGENT_PATH);
+    manager.call::<_, _, ()>("RegisterAgent", &(&agent_path, "DisplayYesNo"))?;
+    manager.call("RequestDefaultAgent", &(&agent_path,))
+}
+
+/// What the agent asks the user to allow
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentRequest {
+    /// Pairing, with the passkey both sides should show if there is one
+    Pair(Option<u32>),
+    /// A device that is not trusted wants to use a service, given by its UUID
+    Service(String),
+}
+
+/// Worded to follow "use", e.g. "Allow Phone to use audio?"
+pub fn service_name(uuid: &str) -> &'static str {
+    // Standard services are 0000xxxx-0000-1000-8000-00805f9b34fb
+    let short = uuid.get(4..8).unwrap_or_default().to_ascii_lowercase();
+    match short.as_str() {
+        "110a" | "110b" | "110d" => "audio",
+        "1108" | "1112" | "111e" | "111f" => "headset audio",
+        "110c" | "110e" | "110f" => "media controls",
+        "1124" => "input",
+        "1105" | "1106" => "file transfer",
+        "1115" | "1116" | "1117" => "network sharing",
+        _ => "a service",
+    }
+}
+
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.bluez.Error")]
+enum AgentError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    Rejected(String),
+}
+
+/// BlueZ pairing agent forwarding confirmation requests to the control center
+struct Agent {
+    sender: relm4::Sender<ConfMessage>,
+}
+
+impl Agent {
+    async fn confirm(
+        &self,
+        device: OwnedObjectPath,
+        request: AgentRequest,
+    ) -> Result<(), AgentError> {
+        let rejected = match request {
+            AgentRequest::Pair(_) => "Pairing rejected",
+            AgentRequest::Service(_) => "Service not authorized",
+        };
+        let (reply, response) = relm4::channel();
+        self.sender.emit(ConfMessage::BluetoothConfirm {
+            device: device.to_string(),
+            request,
+            reply,
+        });
+        match response.recv().await {
+            Some(true) => Ok(()),
+            _ => Err(AgentError::Rejected(rejected.to_string())),
+        }
+    }
+}
+
+#[zbus::interface(name = "org.bluez.Agent1")]
+impl Agent {
+    fn release(&self) {}
+
+    fn request_pin_code(&self, _device: OwnedObjectPath) -> Result<String, AgentError> {
+        Err(AgentError::Rejected(
+            "PIN entry is not supported".to_string(),
+        ))
+    }
+
+    fn display_pin_code(&self, device: OwnedObjectPath, pincode: String) {
+        self.sender.emit(ConfMessage::BluetoothDisplayCode {
+            device: device.to_string(),
+            code: pincode,
+        });
+    }
+
+    fn request_passkey(&self, _device: OwnedObjectPath) -> Result<u32, AgentError> {
+        Err(AgentError::Rejected(
+            "Passkey entry is not supported".to_string(),
+        ))
+    }
+
+    fn display_passkey(&self, device: OwnedObjectPath, passkey: u32, entered: u16) {
+        // BlueZ calls this again for every key typed on the remote keyboard
+        if entered == 0 {
+            self.sender.emit(ConfMessage::BluetoothDisplayCode {
+                device: device.to_string(),
+                code: format!("{:06}", passkey),
+            });
+        }
+    }
+
+    async fn request_confirmation(
+        &self,
+        device: OwnedObjectPath,
+        passkey: u32,
+    ) -> Result<(), AgentError> {
+        self.confirm(device, AgentRequest::Pair(Some(passkey)))
+            .await
+    }
+
+    async fn request_authorization(&self, device: OwnedObjectPath) -> Result<(), AgentError> {
+        self.confirm(device, AgentRequest::Pair(None)).await
+    }
+
+    async fn authorize_service(
+        &self,
+        device: OwnedObjectPath,
+        uuid: String,
+    ) -> Result<(), AgentError> {
+        self.confirm(device, AgentRequest::Service(uuid)).await
+    }
+
+    fn cancel(&self) {
+        self.sender.emit(ConfMessage::BluetoothAgentCancel);
+    }
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::Path;
+use zbus::blocking::Connection;
+
+/// Which kind of light a backlight device controls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BacklightKind {
+    Screen,
+    Keyboard,
+}
+
+/// A backlight device from sysfs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backlight {
+    /// sysfs class the device lives in, as expected by logind
+    pub subsystem: &'static str,
+    pub name: String,
+    pub brightness: u32,
+    pub max_brightness: u32,
+}
+
+impl Backlight {
+    pub fn percent(&self) -> u32 {
+        if self.max_brightness == 0 {
+            return 0;
+        }
+        (self.brightness * 100 + self.max_brightness / 2) / self.max_brightness
+    }
+}
+
+fn read_value(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Finds the first backlight of the given kind in /sys/class/backlight or /sys/class/leds
+pub fn read_backlight(kind: BacklightKind) -> Option<Backlight> {
+    let subsystem = match kind {
+        BacklightKind::Screen => "backlight",
+        BacklightKind::Keyboard => "leds",
+    };
+    let mut entries: Vec<_> = fs::read_dir(Path::new("/sys/class").join(subsystem))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            kind == BacklightKind::Screen
+                || path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().contains("kbd_backlight"))
+        })
+        .collect();
+    // Like logind and GNOME, prefer firmware and platform interfaces over raw driver ones,
+    // e.g. acpi_video0 often doesn't work where intel_backlight does
+    entries.sort_by_cached_key(|path| (type_rank(path), path.clone()));
+
+    entries.into_iter().find_map(|path| {
+        Some(Backlight {
+            subsystem,
+            name: path.file_name()?.to_string_lossy().into_owned(),
+            brightness: read_value(&path.join("brightness"))?,
+            max_brightness: read_value(&path.join("max_brightness"))?,
+        })
+    })
+}
+
+/// Order of a backlight's `type`; keyboard LEDs have none and keep their name order
+fn type_rank(path: &Path) -> u8 {
+    match fs::read_to_string(path.join("type"))
+        .as_deref()
+        .map(str::trim)
+    {
+        Ok("firmware") => 0,
+        Ok("platform") => 1,
+        Ok("raw") => 2,
+        _ => 3,
+    }
+}
+
+/// Raw brightness after scrolling `step` percent up or down. Moves at least one unit, so
+/// devices with few levels don't get stuck, and never turns the light fully off.
+pub fn stepped_brightness(backlight: &Backlight, step: i32) -> u32 {
+    let max = backlight.max_brightness as i32;
+    let units = (step.abs() * max / 100).max(1) * step.signum();
+    (backlight.brightness as i32 + units).clamp(1.min(max), max) as u32
+}
+
+/// Sets brightness in percent through logind, which needs neither root nor udev rules
+pub fn set_brightness(backlight: &Backlight, percent: u32) -> zbus::Result<()> {
+    set_raw_brightness(backlight, percent_to_raw(backlight, percent))
+}
+
+/// Raw brightness for a percentage. Screens with few levels would round low percentages
+/// to 0 and turn off, so they keep at least one unit; keyboard lights may go dark.
+fn percent_to_raw(backlight: &Backlight, percent: u32) -> u32 {
+    let value = (percent.min(100) * backlight.max_brightness + 50) / 100;
+    if backlight.subsystem == "backlight" {
+        value.max(1.min(backlight.max_brightness))
+    } else {
+        value
+    }
+}
+
+/// Sets brightness in the device's own units
+pub fn set_raw_brightness(backlight: &Backlight, value: u32) -> zbus::Result<()> {
+    let conn = Connection::system()?;
+    let proxy = zbus::blocking::Proxy::new(
+        &conn,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1/session/auto",
+        "org.freedesktop.login1.Session",
+    )?;
+    proxy.call(
+        "SetBrightness",
+        &(backlight.subsystem, backlight.name.as_str(), value),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backlight(brightness: u32, max_brightness: u32) -> Backlight {
+        Backlight {
+            subsystem: "backlight",
+            name: "intel_backlight".to_string(),
+            brightness,
+            max_brightness,
+        }
+    }
+
+    #[test]
+    fn steps_five_percent() {
+        assert_eq!(stepped_brightness(&backlight(500, 1000), 5), 550);
+        assert_eq!(stepped_brightness(&backlight(500, 1000), -5), 450);
+    }
+
+    #[test]
+    fn steps_at_least_one_unit() {
+        assert_eq!(stepped_brightness(&backlight(3, 7), 5), 4);
+        assert_eq!(stepped_brightness(&backlight(3, 7), -5), 2);
+    }
+
+    #[test]
+    fn low_percentages_keep_the_screen_lit() {
+        assert_eq!(percent_to_raw(&backlight(3, 7), 1), 1);
+        assert_eq!(percent_to_raw(&backlight(3, 7), 50), 4);
+        assert_eq!(percent_to_raw(&backlight(3, 7), 100), 7);
+        let keyboard = Backlight {
+            subsystem: "leds",
+            ..backlight(1, 3)
+        };
+        assert_eq!(percent_to_raw(&keyboard, 0), 0);
+    }
+
+    #[test]
+    fn stays_lit_and_within_range() {
+        assert_eq!(stepped_brightness(&backlight(1, 7), -5), 1);
+        assert_eq!(stepped_brightness(&backlight(30, 1000), -5), 1);
+        assert_eq!(stepped_brightness(&backlight(7, 7), 5), 7);
+    }
+}
@@ -0,0 +1,850 @@
+use crate::config::expand_home;
+use chrono::{
+    DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Weekday,
+};
+use relm4::adw::glib;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Stops rules that never match, like the 30th of February, from expanding forever
+const MAX_PERIODS: u32 = 20_000;
+
+/// One occurrence of an event
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub all_day: bool,
+}
+
+impl Event {
+    /// "09:30 – 10:00" or "All day"
+    pub fn time_range(&self) -> String {
+        if self.all_day {
+            "All day".to_string()
+        } else {
+            format!(
+                "{} – {}",
+                self.start.format("%H:%M"),
+                self.end.format("%H:%M")
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone)]
+struct RecurrenceRule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<DateTime<Local>>,
+    /// Weekdays, with their ordinal in the month for monthly and yearly rules, e.g. 2TU
+    /// is (Some(2), Tue) and -1FR the last Friday
+    by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of the month, negative ones counting from its end
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+}
+
+/// The clock a DATE-TIME is written in
+#[derive(Debug, Clone, PartialEq)]
+enum Zone {
+    /// Floating times and dates, and zones glib doesn't know
+    Local,
+    Utc,
+    /// An IANA zone from TZID
+    Named(String),
+}
+
+impl Zone {
+    fn from_tzid(tzid: Option<&String>) -> Zone {
+        match tzid {
+            Some(tzid) if glib::TimeZone::from_identifier(Some(tzid)).is_some() => {
+                Zone::Named(tzid.clone())
+            }
+            _ => Zone::Local,
+        }
+    }
+
+    /// The moment a wall clock time in this zone refers to
+    fn to_local(&self, naive: NaiveDateTime) -> Option<DateTime<Local>> {
+        match self {
+            Zone::Local => local_datetime(naive),
+            Zone::Utc => Some(naive.and_utc().with_timezone(&Local)),
+            Zone::Named(zone) => zoned_datetime(naive, zone),
+        }
+    }
+
+    /// What a clock in this zone shows at `instant`
+    fn wall_time(&self, instant: DateTime<Local>) -> NaiveDateTime {
+        match self {
+            Zone::Local => instant.naive_local(),
+            Zone::Utc => instant.naive_utc(),
+            Zone::Named(zone) => zoned_wall_time(instant, zone).unwrap_or(instant.naive_local()),
+        }
+    }
+}
+
+/// A VEVENT with its recurrence rule, expanded into occurrences on demand
+#[derive(Debug, Clone)]
+struct EventSource {
+    uid: String,
+    summary: String,
+    location: Option<String>,
+    start: DateTime<Local>,
+    /// DTSTART as written, recurrences repeat it in `zone` so they follow that zone's DST
+    wall_start: NaiveDateTime,
+    zone: Zone,
+    duration: Duration,
+    all_day: bool,
+    rule: Option<RecurrenceRule>,
+    exceptions: Vec<DateTime<Local>>,
+    /// Start of the occurrence this event replaces, from RECURRENCE-ID
+    replaces: Option<DateTime<Local>>,
+    /// STATUS:CANCELLED, kept only to remove the occurrence it replaces
+    cancelled: bool,
+}
+
+/// Events from the configured .ics files and vdir directories
+#[derive(Debug, Default)]
+pub struct Calendar {
+    sources: Vec<EventSource>,
+}
+
+impl Calendar {
+    /// Reads every .ics file in the given files and directories, skipping unreadable ones
+    pub fn load(paths: &[String]) -> Calendar {
+        let mut files = Vec::new();
+        for path in paths {
+            collect_ics_files(&expand_home(path), &mut files);
+        }
+
+        Calendar::from_sources(
+            files
+                .iter()
+                .filter_map(|file| fs::read_to_string(file).ok())
+                .flat_map(|content| parse_events(&content))
+                .collect(),
+        )
+    }
+
+    fn from_sources(mut sources: Vec<EventSource>) -> Calendar {
+        // Moved or edited occurrences replace the one generated by the rule
+        let replaced: Vec<(String, DateTime<Local>)> = sources
+            .iter()
+            .filter_map(|source| Some((source.uid.clone(), source.replaces?)))
+            .collect();
+        for source in sources.iter_mut().filter(|source| source.rule.is_some()) {
+            source.exceptions.extend(
+                replaced
+                    .iter()
+                    .filter(|(uid, _)| *uid == source.uid)
+                    .map(|(_, start)| *start),
+            );
+        }
+
+        Calendar { sources }
+    }
+
+    /// Occurrences overlapping the given day, all-day events first, then by start time
+    pub fn events_on(&self, date: NaiveDate) -> Vec<Event> {
+        let Some(day_start) = local_datetime(date.and_time(NaiveTime::MIN)) else {
+            return Vec::new();
+        };
+        let day_end = day_start + Duration::days(1);
+        let mut events = self.events_between(day_start, day_end);
+        events.sort_by_key(|event| (!event.all_day, event.start));
+        events
+    }
+
+    /// The first timed event starting after `now` and before `now + within`
+    pub fn next_event(&self, now: DateTime<Local>, within: Duration) -> Option<Event> {
+        self.events_between(now, now + within)
+            .into_iter()
+            .filter(|event| !event.all_day && event.start >= now)
+            .min_by_key(|event| event.start)
+    }
+
+    fn events_between(&self, from: DateTime<Local>, to: DateTime<Local>) -> Vec<Event> {
+        self.sources
+            .iter()
+            .filter(|source| !source.cancelled)
+            .flat_map(|source| {
+                source
+                    .occurrences(from - source.duration, to)
+                    .into_iter()
+                    .filter(|start| *start < to && *start + source.duration > from)
+                    .map(|start| Event {
+                        summary: source.summary.clone(),
+                        location: source.location.clone(),
+                        start,
+                        end: start + source.duration,
+                        all_day: source.all_day,
+                    })
+            })
+            .collect()
+    }
+}
+
+impl EventSource {
+    /// Start times of occurrences beginning before `before`, leaving out most of those
+    /// before `after` without expanding them
+    fn occurrences(&self, after: DateTime<Local>, before: DateTime<Local>) -> Vec<DateTime<Local>> {
+        let Some(rule) = &self.rule else {
+            return vec![self.start];
+        };
+
+        let first = self.wall_start;
+        // COUNT includes every earlier occurrence, so those rules expand from the start
+        let skipped = match rule.count {
+            Some(_) => 0,
+            None => rule.periods_before(first, self.zone.wall_time(after)),
+        };
+        let mut starts = Vec::new();
+        // COUNT includes occurrences removed by EXDATE
+        let mut generated = 0;
+        for period in skipped..skipped.saturating_add(MAX_PERIODS) {
+            for candidate in rule.period_starts(first, period) {
+                if candidate < first {
+                    continue;
+                }
+                let Some(start) = self.zone.to_local(candidate) else {
+                    continue;
+                };
+                if start >= before
+                    || rule.until.is_some_and(|until| start > until)
+                    || rule.count.is_some_and(|count| generated >= count)
+                {
+                    return starts;
+                }
+                generated += 1;
+                if !self.exceptions.contains(&start) {
+                    starts.push(start);
+                }
+            }
+        }
+        starts
+    }
+}
+
+impl RecurrenceRule {
+    /// Number of whole periods that end before `target`, one less to be safe
+    fn periods_before(&self, first: NaiveDateTime, target: NaiveDateTime) -> u32 {
+        let elapsed = match self.frequency {
+            Frequency::Daily => (target - first).num_days(),
+            Frequency::Weekly => (target - first).num_weeks(),
+            Frequency::Monthly => {
+                i64::from(target.year() - first.year()) * 12 + i64::from(target.month())
+                    - i64::from(first.month())
+            }
+            Frequency::Yearly => i64::from(target.year() - first.year()),
+        };
+        (elapsed / i64::from(self.interval) - 1).clamp(0, u32::MAX.into()) as u32
+    }
+
+    /// Candidate starts in the `period`th interval after the first occurrence
+    fn period_starts(&self, first: NaiveDateTime, period: u32) -> Vec<NaiveDateTime> {
+        let steps = period.saturating_mul(self.interval);
+        let time = first.time();
+        let dates: Vec<NaiveDate> = match self.frequency {
+            Frequency::Daily => first
+                .date()
+                .checked_add_days(chrono::Days::new(steps.into()))
+                .filter(|date| {
+                    self.by_day.is_empty()
+                        || self.by_day.iter().any(|(_, day)| *day == date.weekday())
+                })
+                .into_iter()
+                .collect(),
+            Frequency::Weekly => {
+                let Some(week_start) = first
+                    .date()
+                    .checked_add_days(chrono::Days::new(u64::from(steps) * 7))
+                else {
+                    return Vec::new();
+                };
+                if self.by_day.is_empty() {
+                    vec![week_start]
+                } else {
+                    let monday = week_start.week(Weekday::Mon).first_day();
+                    let mut days: Vec<NaiveDate> = self
+                        .by_day
+                        .iter()
+                        .map(|(_, day)| monday + Duration::days(day.num_days_from_monday().into()))
+                        .collect();
+                    days.sort();
+                    days.dedup();
+                    days
+                }
+            }
+            Frequency::Monthly => {
+                let Some(month) = first
+                    .date()
+                    .with_day(1)
+                    .and_then(|start| start.checked_add_months(Months::new(steps)))
+                else {
+                    return Vec::new();
+                };
+                self.days_in_month(first.date(), month)
+            }
+            Frequency::Yearly => {
+                let Ok(year) = i32::try_from(steps).map(|steps| first.year().saturating_add(steps))
+                else {
+                    return Vec::new();
+                };
+                let months = match (self.by_month.is_empty(), self.by_month_day.is_empty()) {
+                    (false, _) => self.by_month.clone(),
+                    // BYMONTHDAY alone repeats in every month of the year
+                    (true, false) => (1..=12).collect(),
+                    (true, true) => vec![first.month()],
+                };
+                months
+                    .into_iter()
+                    .filter_map(|month| NaiveDate::from_ymd_opt(year, month, 1))
+                    .flat_map(|month| self.days_in_month(first.date(), month))
+                    .collect()
+            }
+        };
+        dates
+            .into_iter()
+            .filter(|date| self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            .map(|date| date.and_time(time))
+            .collect()
+    }
+
+    /// The days matching BYMONTHDAY and BYDAY in the month starting on `month`, or the
+    /// day of the first occurrence without them
+    fn days_in_month(&self, first: NaiveDate, month: NaiveDate) -> Vec<NaiveDate> {
+        let length = month
+            .checked_add_months(Months::new(1))
+            .map(|next| (next - month).num_days() as u32)
+            .unwrap_or(31);
+        let mut days: Vec<u32> = if !self.by_month_day.is_empty() {
+            self.by_month_day
+                .iter()
+                .filter_map(|day| match *day {
+                    1.. => u32::try_from(*day).ok(),
+                    ..0 => (length as i32 + 1).checked_add(*day).map(|day| day as u32),
+                    0 => None,
+                })
+                .filter(|day| (1..=length).contains(day))
+                .filter(|day| {
+                    // BYDAY narrows the days down when both are given
+                    self.by_day.is_empty()
+                        || self.by_day.iter().any(|(_, weekday)| {
+                            month
+                                .with_day(*day)
+                                .is_some_and(|date| date.weekday() == *weekday)
+                        })
+                })
+                .collect()
+        } else if !self.by_day.is_empty() {
+            self.by_day
+                .iter()
+                .flat_map(|(ordinal, weekday)| {
+                    let matching: Vec<u32> = (1..=length)
+                        .filter(|day| {
+                            month
+                                .with_day(*day)
+                                .is_some_and(|date| date.weekday() == *weekday)
+                        })
+                        .collect();
+                    match ordinal {
+                        None => matching,
+                        Some(n @ 1..) => {
+                            matching.get(*n as usize - 1).copied().into_iter().collect()
+                        }
+                        Some(n) => matching
+                            .len()
+                            .checked_sub(n.unsigned_abs() as usize)
+                            .and_then(|index| matching.get(index).copied())
+                            .into_iter()
+                            .collect(),
+                    }
+                })
+                .collect()
+        } else {
+            vec![first.day()]
+        };
+        days.sort();
+        days.dedup();
+        days.into_iter()
+            .filter_map(|day| month.with_day(day))
+            .collect()
+    }
+}
+
+/// A vdir is a directory of collections, each a directory of .ics files
+fn collect_ics_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            collect_ics_files(&entry.path(), files);
+        }
+    } else if path.extension().is_some_and(|extension| extension == "ics") {
+        files.push(path.to_path_buf());
+    }
+}
+
+fn local_datetime(naive: NaiveDateTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&naive).earliest()
+}
+
+/// The wall clock time in an IANA zone at `instant`
+fn zoned_wall_time(instant: DateTime<Local>, zone: &str) -> Option<NaiveDateTime> {
+    let zone = glib::TimeZone::from_identifier(Some(zone))?;
+    let zoned = glib::DateTime::from_unix_utc(instant.timestamp())
+        .and_then(|utc| utc.to_timezone(&zone))
+        .ok()?;
+    NaiveDate::from_ymd_opt(
+        zoned.year(),
+        zoned.month() as u32,
+        zoned.day_of_month() as u32,
+    )?
+    .and_hms_opt(
+        zoned.hour() as u32,
+        zoned.minute() as u32,
+        zoned.second() as u32,
+    )
+}
+
+/// Converts a wall clock time in an IANA zone, which chrono alone cannot do
+fn zoned_datetime(naive: NaiveDateTime, zone: &str) -> Option<DateTime<Local>> {
+    let zone = glib::TimeZone::from_identifier(Some(zone))?;
+    let zoned = glib::DateTime::new(
+        &zone,
+        naive.year(),
+        naive.month() as i32,
+        naive.day() as i32,
+        naive.hour() as i32,
+        naive.minute() as i32,
+        naive.second() as f64,
+    )
+    .ok()?;
+    Local.timestamp_opt(zoned.to_unix(), 0).single()
+}
+
+/// Parses DATE and DATE-TIME values into the time as written and its zone, returning
+/// whether it was a plain date
+fn parse_wall_time(
+    value: &str,
+    params: &HashMap<String, String>,
+) -> Option<(NaiveDateTime, Zone, bool)> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return Some((date.and_time(NaiveTime::MIN), Zone::Local, true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((naive, Zone::Utc, false));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((naive, Zone::from_tzid(params.get("TZID")), false))
+}
+
+/// Parses DATE and DATE-TIME values, returning whether it was a plain date
+fn parse_date_time(
+    value: &str,
+    params: &HashMap<String, String>,
+) -> Option<(DateTime<Local>, bool)> {
+    let (naive, zone, all_day) = parse_wall_time(value, params)?;
+    Some((zone.to_local(naive)?, all_day))
+}
+
+/// Parses an RFC 5545 DURATION such as "PT1H30M" or "P1D"
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.trim_start_matches('+')),
+    };
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let amount: i64 = number.parse().ok()?;
+                number.clear();
+                duration += match unit {
+                    'W' => Duration::weeks(amount),
+                    'D' => Duration::days(amount),
+                    'H' => Duration::hours(amount),
+                    'M' => Duration::minutes(amount),
+                    'S' => Duration::seconds(amount),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(if negative { -duration } else { duration })
+}
+
+/// Parses a BYDAY entry like "TU", "2TU" or "-1FR"
+fn parse_weekday(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let split = value.len().checked_sub(2)?;
+    let (ordinal, day) = value.split_at_checked(split)?;
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(ordinal.trim_start_matches('+').parse().ok()?).filter(|n| *n != 0),
+    };
+    let day = match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    Some((ordinal, day))
+}
+
+/// Comma separated numbers of a BY* part, None when one of them is not a number
+fn parse_numbers<T: std::str::FromStr>(value: Option<&&str>) -> Option<Vec<T>> {
+    match value {
+        Some(value) => value.split(',').map(|number| number.parse().ok()).collect(),
+        None => Some(Vec::new()),
+    }
+}
+
+fn parse_rule(value: &str) -> Option<RecurrenceRule> {
+    let parts: HashMap<&str, &str> = value
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .collect();
+    let frequency = match *parts.get("FREQ")? {
+        "DAILY" => Frequency::Daily,
+        "WEEKLY" => Frequency::Weekly,
+        "MONTHLY" => Frequency::Monthly,
+        "YEARLY" => Frequency::Yearly,
+        _ => return None,
+    };
+    // Rules using parts not handled here are dropped rather than shown on wrong days,
+    // leaving just the first occurrence
+    if parts
+        .keys()
+        .any(|part| part.starts_with("BY") && !matches!(*part, "BYDAY" | "BYMONTHDAY" | "BYMONTH"))
+    {
+        return None;
+    }
+    let mut by_day = match parts.get("BYDAY") {
+        Some(days) => days
+            .split(',')
+            .map(parse_weekday)
+            .collect::<Option<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    let by_month_day = parse_numbers(parts.get("BYMONTHDAY"))?;
+    let by_month: Vec<u32> = parse_numbers(parts.get("BYMONTH"))?;
+    match frequency {
+        // Ordinals only count within a month or year
+        Frequency::Daily | Frequency::Weekly => {
+            by_day.iter_mut().for_each(|(ordinal, _)| *ordinal = None);
+        }
+        Frequency::Monthly => {}
+        // Counting weekdays through the whole year, like the 20th Monday, is not supported
+        Frequency::Yearly if by_month.is_empty() && !by_day.is_empty() => return None,
+        Frequency::Yearly => {}
+    }
+    Some(RecurrenceRule {
+        frequency,
+        interval: parts
+            .get("INTERVAL")
+            .and_then(|interval| interval.parse().ok())
+            .filter(|interval| *interval > 0)
+            .unwrap_or(1),
+        count: parts.get("COUNT").and_then(|count| count.parse().ok()),
+        until: parts
+            .get("UNTIL")
+            .and_then(|until| parse_date_time(until, &HashMap::new()))
+            // A date-only UNTIL includes that whole day
+            .map(|(until, all_day)| {
+                if all_day {
+                    until + Duration::days(1) - Duration::seconds(1)
+                } else {
+                    until
+                }
+            }),
+        by_day,
+        by_month_day,
+        by_month,
+    })
+}
+
+/// Undoes line folding and splits "NAME;PARAM=x:value" lines
+fn content_lines(content: &str) -> Vec<(String, HashMap<String, String>, String)> {
+    let mut unfolded: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), unfolded.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => unfolded.push(line.to_string()),
+        }
+    }
+
+    unfolded
+        .into_iter()
+        .filter_map(|line| {
+            // Quoted parameter values may contain ':' and ';'
+            let colon = find_unquoted(&line, ':')?;
+            let (head, value) = (&line[..colon], &line[colon + 1..]);
+            let mut head = split_unquoted(head, ';');
+            let name = head.next()?.to_ascii_uppercase();
+            let params = head
+                .filter_map(|param| param.split_once('='))
+                .map(|(key, value)| {
+                    (
+                        key.to_ascii_uppercase(),
+                        value.trim_matches('"').to_string(),
+                    )
+                })
+                .collect();
+            Some((name, params, value.to_string()))
+        })
+        .collect()
+}
+
+fn find_unquoted(text: &str, separator: char) -> Option<usize> {
+    let mut quoted = false;
+    text.find(|c| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == separator && !quoted
+    })
+}
+
+fn split_unquoted(text: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    text.split(move |c| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == separator && !quoted
+    })
+}
+
+/// Undoes TEXT escaping in one pass, so an escaped backslash can't start another escape
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push(' '),
+            Some(escaped @ (',' | ';' | '\\')) => unescaped.push(escaped),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Each property of a VEVENT with the parameters and value of every line setting it
+type Properties = HashMap<String, Vec<(HashMap<String, String>, String)>>;
+
+fn parse_events(content: &str) -> Vec<EventSource> {
+    let mut events = Vec::new();
+    let mut current: Option<Properties> = None;
+
+    for (name, params, value) in content_lines(content) {
+        match (name.as_str(), value.as_str()) {
+            ("BEGIN", "VEVENT") => current = Some(HashMap::new()),
+            ("END", "VEVENT") => {
+                if let Some(event) = current
+                    .take()
+                    .and_then(|properties| event_from(&properties))
+                {
+                    events.push(event);
+                }
+            }
+            _ => {
+                if let Some(properties) = &mut current {
+                    properties.entry(name).or_default().push((params, value));
+                }
+            }
+        }
+    }
+    events
+}
+
+fn event_from(properties: &Properties) -> Option<EventSource> {
+    let first = |name: &str| properties.get(name).and_then(|values| values.first());
+    let date_time = |name: &str| {
+        let (params, value) = first(name)?;
+        parse_date_time(value, params)
+    };
+
+    let (wall_start, zone, all_day) =
+        first("DTSTART").and_then(|(params, value)| parse_wall_time(value, params))?;
+    let start = zone.to_local(wall_start)?;
+    let duration = match date_time("DTEND") {
+        Some((end, _)) => end - start,
+        None => first("DURATION")
+            .and_then(|(_, value)| parse_duration(value))
+            .unwrap_or_else(|| {
+                if all_day {
+                    Duration::days(1)
+                } else {
+                    Duration::zero()
+                }
+            }),
+    };
+    let exceptions = properties
+        .get("EXDATE")
+        .into_iter()
+        .flatten()
+        .flat_map(|(params, value)| {
+            value
+                .split(',')
+                .filter_map(|value| parse_date_time(value, params))
+                .map(|(start, _)| start)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Some(EventSource {
+        uid: first("UID").map(|(_, uid)| uid.clone()).unwrap_or_default(),
+        summary: first("SUMMARY")
+            .map(|(_, summary)| unescape(summary))
+            .unwrap_or_else(|| "Untitled event".to_string()),
+        location: first("LOCATION")
+            .map(|(_, location)| unescape(location))
+            .filter(|location| !location.is_empty()),
+        start,
+        wall_start,
+        zone,
+        duration,
+        all_day,
+        rule: first("RRULE").and_then(|(_, rule)| parse_rule(rule)),
+        exceptions,
+        replaces: date_time("RECURRENCE-ID").map(|(start, _)| start),
+        cancelled: first("STATUS").is_some_and(|(_, status)| status == "CANCELLED"),
+    })
+}
+
+/// Today's date in local time
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Calendar dates arrive as a glib DateTime
+pub fn date_from_glib(date: &glib::DateTime) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(date.year(), date.month() as u32, date.day_of_month() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recurring(rule: &str, start: &str) -> Calendar {
+        let ics = format!(
+            "BEGIN:VEVENT\nUID:test\nSUMMARY:Meeting\nDTSTART:{}\nDURATION:PT1H\nRRULE:{}\nEND:VEVENT\n",
+            start, rule
+        );
+        Calendar::from_sources(parse_events(&ics))
+    }
+
+    /// Days with an occurrence among the `days` days from `from`
+    fn occurrence_days(calendar: &Calendar, from: &str, days: i64) -> Vec<String> {
+        let from = NaiveDate::parse_from_str(from, "%Y-%m-%d").unwrap();
+        (0..days)
+            .map(|offset| from + Duration::days(offset))
+            .filter(|day| !calendar.events_on(*day).is_empty())
+            .map(|day| day.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn monthly_by_weekday_ordinal() {
+        let second_tuesday = recurring("FREQ=MONTHLY;BYDAY=2TU", "20250114T100000");
+        assert_eq!(
+            occurrence_days(&second_tuesday, "2025-01-01", 120),
+            ["2025-01-14", "2025-02-11", "2025-03-11", "2025-04-08"]
+        );
+        let last_friday = recurring("FREQ=MONTHLY;BYDAY=-1FR", "20250131T100000");
+        assert_eq!(
+            occurrence_days(&last_friday, "2025-01-01", 90),
+            ["2025-01-31", "2025-02-28", "2025-03-28"]
+        );
+    }
+
+    #[test]
+    fn monthly_by_month_day() {
+        let calendar = recurring("FREQ=MONTHLY;BYMONTHDAY=15,-1", "20250115T100000");
+        assert_eq!(
+            occurrence_days(&calendar, "2025-01-01", 60),
+            ["2025-01-15", "2025-01-31", "2025-02-15", "2025-02-28"]
+        );
+    }
+
+    #[test]
+    fn yearly_by_month_and_weekday() {
+        let calendar = recurring("FREQ=YEARLY;BYMONTH=11;BYDAY=4TH", "20241128T100000");
+        assert_eq!(occurrence_days(&calendar, "2026-11-01", 30), ["2026-11-26"]);
+    }
+
+    #[test]
+    fn expansion_starts_near_the_window() {
+        let calendar = recurring("FREQ=WEEKLY;BYDAY=MO,WE", "19000101T090000");
+        assert_eq!(
+            occurrence_days(&calendar, "2026-10-19", 7),
+            ["2026-10-19", "2026-10-21"]
+        );
+    }
+
+    #[test]
+    fn unsupported_rule_keeps_first_occurrence_only() {
+        let calendar = recurring("FREQ=MONTHLY;BYSETPOS=-1;BYDAY=MO,TU", "20250131T100000");
+        assert_eq!(occurrence_days(&calendar, "2025-01-01", 90), ["2025-01-31"]);
+    }
+
+    #[test]
+    fn cancelled_occurrences_are_left_out() {
+        let ics = "BEGIN:VEVENT\nUID:weekly\nSUMMARY:Standup\nDTSTART:20250106T090000\n\
+                   DURATION:PT15M\nRRULE:FREQ=WEEKLY\nEND:VEVENT\n\
+                   BEGIN:VEVENT\nUID:weekly\nRECURRENCE-ID:20250113T090000\n\
+                   DTSTART:20250113T090000\nSTATUS:CANCELLED\nEND:VEVENT\n\
+                   BEGIN:VEVENT\nUID:party\nSUMMARY:Party\nDTSTART:20250108T200000\n\
+                   STATUS:CANCELLED\nEND:VEVENT\n";
+        let calendar = Calendar::from_sources(parse_events(ics));
+        assert_eq!(occurrence_days(&calendar, "2025-01-06", 14), ["2025-01-06"]);
+    }
+
+    #[test]
+    fn unescapes_in_one_pass() {
+        assert_eq!(
+            unescape("C:\\\\new\\, room\\;\\nfloor 2"),
+            "C:\\new, room; floor 2"
+        );
+    }
+
+    #[test]
+    fn quoted_parameters_may_contain_colons() {
+        let lines = content_lines("DTSTART;TZID=\"Custom:Zone\";X-A=1:20250101T100000\n");
+        let (name, params, value) = &lines[0];
+        assert_eq!(name, "DTSTART");
+        assert_eq!(params["TZID"], "Custom:Zone");
+        assert_eq!(value, "20250101T100000");
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{Datelike, Local, Timelike};
+use relm4::adw::glib;
+use std::time::Duration;
+
+/// Conversions that change every second
+const SECOND_CONVERSIONS: [&str; 6] = ["S", "s", "T", "r", "X", "c"];
+
+/// A clock for one of the extra time zones in the bar or the calendar popover
+pub struct ZoneClock {
+    /// City part of the zone identifier, e.g. "New York"
+    pub city: String,
+    zone: glib::TimeZone,
+}
+
+impl ZoneClock {
+    /// Skips unknown identifiers with a warning
+    pub fn load(identifiers: &[String]) -> Vec<ZoneClock> {
+        identifiers
+            .iter()
+            .filter_map(|identifier| {
+                let Some(zone) = glib::TimeZone::from_identifier(Some(identifier)) else {
+                    eprintln!("Warning: Unknown time zone '{}'", identifier);
+                    return None;
+                };
+                let city = identifier.rsplit('/').next().unwrap_or(identifier);
+                Some(ZoneClock {
+                    city: city.replace('_', " "),
+                    zone,
+                })
+            })
+            .collect()
+    }
+
+    /// Current time in the zone, formatted like `format_local`
+    pub fn format(&self, format: &str) -> String {
+        glib::DateTime::now(&self.zone)
+            .ok()
+            .and_then(|time| time.format(format).ok())
+            .map(String::from)
+            .unwrap_or_default()
+    }
+
+    /// Current time in the zone, with the weekday when it differs from the local one
+    pub fn time(&self) -> String {
+        let local = glib::DateTime::now_local();
+        let zoned = glib::DateTime::now(&self.zone);
+        let (Ok(local), Ok(zoned)) = (local, zoned) else {
+            return String::new();
+        };
+        if zoned.day_of_year() == local.day_of_year() {
+            self.format("%H:%M")
+        } else {
+            self.format("%a %H:%M")
+        }
+    }
+}
+
+/// Formats the local time with strftime-style conversions. Unlike chrono, GLib takes
+/// month and day names from the locale.
+pub fn format_local(format: &str) -> String {
+    glib::DateTime::now_local()
+        .ok()
+        .and_then(|time| time.format(format).ok())
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+fn shows_seconds(format: &str) -> bool {
+    format.split('%').skip(1).any(|conversion| {
+        let conversion = conversion.trim_start_matches(['-', '_', '0', 'E', 'O']);
+        SECOND_CONVERSIONS
+            .iter()
+            .any(|seconds| conversion.starts_with(seconds))
+    })
+}
+
+/// Time until just after the next second, or the next minute when none of the formats
+/// show seconds
+pub fn until_next_tick(formats: &[&str]) -> Duration {
+    let now = Local::now();
+    let into_second = u64::from(now.timestamp_subsec_micros().min(999_999));
+    let micros = if formats.iter().any(|format| shows_seconds(format)) {
+        1_000_000 - into_second
+    } else {
+        u64::from(60 - now.second()) * 1_000_000 - into_second
+    };
+    // Land a little after the boundary rather than just before it
+    Duration::from_micros(micros + 5_000)
+}
+
+/// e.g. "Saturday, October 18, 2026"
+pub fn full_date() -> String {
+    format_local("%A, %B %-d, %Y")
+}
+
+pub fn week_label() -> String {
+    format!("Week {}", Local::now().iso_week().week())
+}
@@ -0,0 +1,329 @@
+use chrono::NaiveTime;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// User configuration read from `$XDG_CONFIG_HOME/adwbar/config.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub bar: BarConfig,
+    pub session: SessionConfig,
+    pub notifications: NotificationsConfig,
+    pub clock: ClockConfig,
+    pub calendar: CalendarConfig,
+    pub timer: TimerConfig,
+    pub theme: ThemeConfig,
+    /// `[[custom]]` modules running user scripts
+    #[serde(rename = "custom")]
+    pub custom_modules: Vec<CustomModuleConfig>,
+}
+
+/// Screen edge the bar sits on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarPosition {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl BarPosition {
+    pub fn is_vertical(&self) -> bool {
+        matches!(self, BarPosition::Left | BarPosition::Right)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BarPosition::Top => "top",
+            BarPosition::Bottom => "bottom",
+            BarPosition::Left => "left",
+            BarPosition::Right => "right",
+        }
+    }
+}
+
+/// What the bar does while a window is fullscreen on its output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FullscreenBehavior {
+    /// Stays on the top layer, above the window in some compositors
+    #[default]
+    Stay,
+    /// Moves to the bottom layer, behind the window
+    Bottom,
+    /// Turns invisible, still reserving its space
+    Hide,
+}
+
+/// Placement and size of the bar windows
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BarConfig {
+    pub position: BarPosition,
+    /// Height of horizontal bars and width of vertical ones in pixels
+    pub thickness: i32,
+    /// Gap in pixels between the bar and the screen edges it touches
+    pub margin: i32,
+    /// Slide the bar off screen until the pointer touches its edge or the overview opens
+    pub autohide: bool,
+    /// Milliseconds the bar stays after the pointer leaves it
+    pub autohide_delay: u32,
+    pub fullscreen: FullscreenBehavior,
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            position: BarPosition::Top,
+            thickness: 24,
+            margin: 0,
+            autohide: false,
+            autohide_delay: 500,
+            fullscreen: FullscreenBehavior::Stay,
+        }
+    }
+}
+
+/// Built-in bar colours
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreset {
+    /// Window colours of the Adwaita light or dark style
+    #[default]
+    Adwaita,
+    /// White on black
+    Black,
+    /// The bar takes the accent colour
+    Accent,
+}
+
+/// Light or dark style of the bar, its popovers and the control center
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    /// Follows the desktop's preference
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// Colours and user CSS
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub preset: ThemePreset,
+    pub color_scheme: ColorScheme,
+    /// CSS loaded over the built-in style, "~/" is expanded. Defaults to style.css next
+    /// to the default config file.
+    pub stylesheet: Option<String>,
+    /// Opacity of the bar background from 0 to 1
+    pub opacity: f64,
+    /// Corner radius in pixels of the bar, or of each section with `pills`
+    pub radius: u32,
+    /// Draw the window list, clock and status area as separate pills on a clear bar
+    pub pills: bool,
+    /// CSS background colour by module name, or by id for custom modules
+    pub module_colors: BTreeMap<String, String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            preset: ThemePreset::Adwaita,
+            color_scheme: ColorScheme::System,
+            stylesheet: None,
+            opacity: 1.0,
+            radius: 0,
+            pills: false,
+            module_colors: BTreeMap::new(),
+        }
+    }
+}
+
+/// Options for the session actions in the control center
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Shell command used to lock the screen, e.g. "swaylock -f".
+    /// When unset the session is locked through logind.
+    pub lock_command: Option<String>,
+    /// Seconds before a confirmed suspend, reboot etc. runs on its own
+    pub confirm_timeout: u32,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            lock_command: None,
+            confirm_timeout: 60,
+        }
+    }
+}
+
+/// Options for the clock and its calendar popover
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    /// strftime-style format of the bar clock, names follow the locale
+    pub format: String,
+    /// Format switched to by right-clicking the clock
+    pub alt_format: Option<String>,
+    pub tooltip_format: String,
+    /// IANA time zones with their own clock in the bar, e.g. "Asia/Tokyo"
+    pub bar_time_zones: Vec<String>,
+    /// Extra IANA time zones shown below the calendar, e.g. "America/New_York"
+    pub time_zones: Vec<String>,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            format: "%H:%M".to_string(),
+            alt_format: None,
+            tooltip_format: "%A, %B %-d, %Y".to_string(),
+            bar_time_zones: Vec::new(),
+            time_zones: Vec::new(),
+        }
+    }
+}
+
+/// Where calendar events come from
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// .ics files or vdir directories as synced by vdirsyncer, "~/" is expanded
+    pub paths: Vec<String>,
+    /// Minutes before a meeting to show it in the bar, 0 to never show it
+    pub remind_before: u32,
+}
+
+/// Options for the countdown and pomodoro timer
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TimerConfig {
+    /// Countdown length the popover starts with
+    pub default_minutes: u32,
+    pub work_minutes: u32,
+    pub break_minutes: u32,
+    pub long_break_minutes: u32,
+    /// Every this many focus phases the break is a long one
+    pub long_break_every: u32,
+    /// Sound file played with paplay when a timer runs out
+    pub sound: Option<String>,
+}
+
+impl Default for TimerConfig {
+    fn default() -> Self {
+        Self {
+            default_minutes: 10,
+            work_minutes: 25,
+            break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+            sound: None,
+        }
+    }
+}
+
+/// A bar module showing the output of a script, compatible with waybar's custom modules
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomModuleConfig {
+    /// Name used for the CSS class `custom-<id>`
+    pub id: String,
+    /// Shell command printing plain text or waybar-style JSON
+    pub exec: String,
+    /// Seconds between runs. Without it the command keeps running and every line it
+    /// prints replaces the module's content.
+    #[serde(default)]
+    pub interval: Option<u64>,
+    /// Label with "{text}" and "{percentage}" filled in
+    #[serde(default = "default_custom_format")]
+    pub format: String,
+    #[serde(default)]
+    pub on_click: Option<String>,
+    #[serde(default)]
+    pub on_click_right: Option<String>,
+    #[serde(default)]
+    pub on_scroll_up: Option<String>,
+    #[serde(default)]
+    pub on_scroll_down: Option<String>,
+}
+
+fn default_custom_format() -> String {
+    "{text}".to_string()
+}
+
+/// Options for the built-in notification daemon
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Take over org.freedesktop.Notifications, off so an existing daemon keeps working
+    pub enabled: bool,
+    /// Seconds a popup stays up when the sender does not choose
+    pub popup_timeout: u32,
+    /// Start of the daily Do Not Disturb period as "HH:MM"
+    pub dnd_from: Option<String>,
+    /// End of the daily Do Not Disturb period, may be earlier than `dnd_from` to span midnight
+    pub dnd_until: Option<String>,
+    /// Turn on Do Not Disturb while a window is fullscreen on the bar's output
+    pub dnd_when_fullscreen: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            popup_timeout: 5,
+            dnd_from: None,
+            dnd_until: None,
+            dnd_when_fullscreen: false,
+        }
+    }
+}
+
+impl NotificationsConfig {
+    /// The Do Not Disturb period, None when unset or not valid "HH:MM" times
+    pub fn dnd_schedule(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let parse =
+            |time: &Option<String>| NaiveTime::parse_from_str(time.as_deref()?, "%H:%M").ok();
+        Some((parse(&self.dnd_from)?, parse(&self.dnd_until)?))
+    }
+}
+
+/// Replaces a leading "~/" with the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Default location of the config file
+pub fn default_config_path() -> PathBuf {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .unwrap_or_default();
+    config_dir.join("adwbar").join("config.toml")
+}
+
+impl Config {
+    /// Loads the config, falling back to defaults when the file is missing or invalid
+    pub fn load(path: Option<&Path>) -> Self {
+        let path = path
+            .map(Path::to_path_buf)
+            .unwrap_or_else(default_config_path);
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("Warning: Invalid config {}: {}", path.display(), e);
+                Config::default()
+            }),
+            Err(_) => Config::default(),
+        }
+    }
+}
@@ -1,24 +1,94 @@
-use crate::connectivity::{ConnectivityStatus, read_bluetooth_status, read_wifi_status};
-use crate::messages::ConfMessage;
+use crate::audio::{
+    AudioBackend, AudioDevice, AudioDevices, AudioWatcher, DeviceKind, change_volume, make_default,
+    microphone_icon, toggle_mute, volume_icon,
+};
+use crate::bluetooth::{
+    AgentRequest, BluetoothAdapter, BluetoothDevice, BluetoothSession, DeviceAction,
+    choose_adapter, read_adapters, read_devices, read_discovering, select_adapter, service_name,
+    watch_adapters,
+};
+use crate::brightness::{Backlight, BacklightKind, read_backlight, set_brightness};
+use crate::config::Config;
+use crate::connectivity::{
+    ConnectivityStatus, ToggleError, read_bluetooth_status, read_wifi_status,
+    set_bluetooth_powered, set_wifi_enabled,
+};
+use crate::messages::{ConfMessage, ConfOutput};
+use crate::mpris::{self, Player, PlayersWatcher, active_player, format_time, select_player};
+use crate::networks::{
+    EthernetDevice, VpnConnection, read_ethernet_devices, read_vpn_connections, set_vpn_active,
+};
+use crate::notifications::{DndReason, Notification};
+use crate::power_profiles::{PowerProfiles, read_power_profiles, set_power_profile};
+use crate::rfkill::{
+    Radio, describe_radios, is_airplane_mode, radio_state, read_radios, set_airplane_mode,
+};
+use crate::session::SessionAction;
 use crate::system_monitor::{read_cpu_usage, read_gpu_usage};
 use gtk4_layer_shell::{Layer, LayerShell};
 use relm4::adw::glib;
 use relm4::adw::prelude::*;
 use relm4::prelude::*;
-use zbus::blocking::Connection;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 pub struct ConfigWindow {
+    config: Config,
     wifi_status: ConnectivityStatus,
     bluetooth_status: ConnectivityStatus,
+    radios: Vec<Radio>,
     cpu_usage: String,
     gpu_usage: String,
+    ethernet_devices: Vec<EthernetDevice>,
+    vpn_connections: Vec<VpnConnection>,
+    networks_list: gtk::ListBox,
+    bluetooth_adapters: Vec<BluetoothAdapter>,
+    adapter_row: adw::ComboRow,
+    /// Blocked while the adapter list is repopulated, which resets the selection
+    adapter_row_handler: Option<glib::SignalHandlerId>,
+    bluetooth_devices: Vec<BluetoothDevice>,
+    bluetooth_busy: HashSet<String>,
+    bluetooth_discovering: bool,
+    bluetooth_list: gtk::ListBox,
+    bluetooth_session: Option<BluetoothSession>,
+    pairing_dialog: Option<adw::AlertDialog>,
+    window: adw::ApplicationWindow,
+    toast_overlay: adw::ToastOverlay,
+    power_profiles: Option<PowerProfiles>,
+    screen_backlight: Option<Backlight>,
+    keyboard_backlight: Option<Backlight>,
+    audio: AudioWatcher,
+    outputs: Vec<AudioDevice>,
+    inputs: Vec<AudioDevice>,
+    output_row: adw::ComboRow,
+    input_row: adw::ComboRow,
+    output_row_handler: Option<glib::SignalHandlerId>,
+    input_row_handler: Option<glib::SignalHandlerId>,
+    notifications: Vec<Notification>,
+    notifications_list: gtk::ListBox,
+    dnd: Option<DndReason>,
+    players: Vec<Player>,
+    players_watcher: PlayersWatcher,
+    media: Option<Player>,
+    player_row: adw::ComboRow,
+    player_row_handler: Option<glib::SignalHandlerId>,
+    media_art: gtk::Image,
+}
+
+pub struct ConfigWindowInit {
+    pub config: Config,
+    /// Changes devices on the bar's audio thread, which sends them back here through the bar
+    pub audio: AudioWatcher,
+    /// Sends the players to the bar, which forwards them here
+    pub players: PlayersWatcher,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for ConfigWindow {
-    type Init = ();
+    type Init = ConfigWindowInit;
     type Input = ConfMessage;
-    type Output = ();
+    type Output = ConfOutput;
 
     view! {
         adw::ApplicationWindow {
@@ -27,82 +97,487 @@ impl SimpleComponent for ConfigWindow {
             set_hide_on_close: true,
             add_css_class: "config-window",
 
-            gtk::Box {
-                set_orientation: gtk::Orientation::Vertical,
-                set_spacing: 0,
+            #[local_ref]
+            toast_overlay -> adw::ToastOverlay {
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 0,
 
-                adw::HeaderBar {
-                    #[wrap(Some)]
-                    set_title_widget = &gtk::Label {
-                        set_css_classes: &["flat"],
-                        set_label: "Control Center",
-                    }
-                },
+                    adw::HeaderBar {
+                        #[wrap(Some)]
+                        set_title_widget = &gtk::Label {
+                            set_css_classes: &["flat"],
+                            set_label: "Control Center",
+                        }
+                    },
+
+                    gtk::ScrolledWindow {
+                        set_vexpand: true,
+                        set_hexpand: true,
 
-                gtk::ScrolledWindow {
-                    set_vexpand: true,
-                    set_hexpand: true,
+                        gtk::Box {
+                            set_orientation: gtk::Orientation::Vertical,
+                            set_spacing: 12,
+                            set_margin_all: 12,
 
-                    gtk::Box {
-                        set_orientation: gtk::Orientation::Vertical,
-                        set_spacing: 12,
-                        set_margin_all: 12,
+                            // Session Section
+                            adw::PreferencesGroup {
+                                set_title: "Session",
 
-                        // System Information Section
-                        adw::PreferencesGroup {
-                            set_title: "System Information",
+                                #[local_ref]
+                                session_box -> gtk::Box {
+                                    set_spacing: 6,
+                                    set_homogeneous: true,
+                                },
+                            },
 
-                            adw::ActionRow {
-                                set_title: "CPU Usage",
-                                add_suffix = &gtk::Label {
+                            // Notifications Section
+                            adw::PreferencesGroup {
+                                set_title: "Notifications",
+                                set_visible: model.config.notifications.enabled,
+                                #[wrap(Some)]
+                                set_header_suffix = &gtk::Button {
+                                    set_label: "Clear All",
+                                    add_css_class: "flat",
                                     #[watch]
-                                    set_label: &model.cpu_usage,
-                                    add_css_class: "dim-label",
-                                }
+                                    set_sensitive: !model.notifications.is_empty(),
+                                    connect_clicked[sender] => move |_| {
+                                        sender.input(ConfMessage::ClearNotifications);
+                                    },
+                                },
+
+                                adw::ActionRow {
+                                    set_title: "Do Not Disturb",
+                                    #[watch]
+                                    set_subtitle: model.dnd.map_or("", |reason| reason.describe()),
+                                    add_suffix = &gtk::Switch {
+                                        set_valign: gtk::Align::Center,
+                                        #[watch]
+                                        #[block_signal(dnd_toggled)]
+                                        set_active: model.dnd.is_some(),
+                                        connect_state_set[sender] => move |_, enabled| {
+                                            sender.output(ConfOutput::SetDoNotDisturb(enabled)).ok();
+                                            glib::Propagation::Proceed
+                                        } @dnd_toggled,
+                                    },
+                                },
+
+                                #[local_ref]
+                                notifications_list -> gtk::ListBox {
+                                    set_margin_top: 12,
+                                    add_css_class: "boxed-list",
+                                    set_selection_mode: gtk::SelectionMode::None,
+                                },
+                            },
+
+                            // System Information Section
+                            adw::PreferencesGroup {
+                                set_title: "System Information",
+
+                                adw::ActionRow {
+                                    set_title: "CPU Usage",
+                                    add_suffix = &gtk::Label {
+                                        #[watch]
+                                        set_label: &model.cpu_usage,
+                                        add_css_class: "dim-label",
+                                    }
+                                },
+
+                                adw::ActionRow {
+                                    set_title: "GPU Usage",
+                                    add_suffix = &gtk::Label {
+                                        #[watch]
+                                        set_label: &model.gpu_usage,
+                                        add_css_class: "dim-label",
+                                    }
+                                },
                             },
 
-                            adw::ActionRow {
-                                set_title: "GPU Usage",
-                                add_suffix = &gtk::Label {
+                            // Display Section
+                            adw::PreferencesGroup {
+                                set_title: "Display",
+                                #[watch]
+                                set_visible: model.screen_backlight.is_some() || model.keyboard_backlight.is_some(),
+
+                                adw::ActionRow {
+                                    set_title: "Screen",
                                     #[watch]
-                                    set_label: &model.gpu_usage,
-                                    add_css_class: "dim-label",
-                                }
+                                    set_visible: model.screen_backlight.is_some(),
+                                    add_prefix = &gtk::Image {
+                                        set_icon_name: Some("display-brightness-symbolic"),
+                                    },
+                                    add_suffix = &gtk::Scale::with_range(gtk::Orientation::Horizontal, 1.0, 100.0, 1.0) {
+                                        set_hexpand: true,
+                                        set_width_request: 220,
+                                        set_valign: gtk::Align::Center,
+                                        #[watch]
+                                        #[block_signal(screen_brightness_changed)]
+                                        set_value: model.screen_backlight.as_ref().map_or(0.0, |b| b.percent() as f64),
+                                        connect_value_changed[sender] => move |scale| {
+                                            sender.input(ConfMessage::SetBrightness(BacklightKind::Screen, scale.value() as u32));
+                                        } @screen_brightness_changed,
+                                    },
+                                },
+
+                                adw::ActionRow {
+                                    set_title: "Keyboard",
+                                    #[watch]
+                                    set_visible: model.keyboard_backlight.is_some(),
+                                    add_prefix = &gtk::Image {
+                                        set_icon_name: Some("keyboard-brightness-symbolic"),
+                                    },
+                                    add_suffix = &gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 1.0) {
+                                        set_hexpand: true,
+                                        set_width_request: 220,
+                                        set_valign: gtk::Align::Center,
+                                        #[watch]
+                                        #[block_signal(keyboard_brightness_changed)]
+                                        set_value: model.keyboard_backlight.as_ref().map_or(0.0, |b| b.percent() as f64),
+                                        connect_value_changed[sender] => move |scale| {
+                                            sender.input(ConfMessage::SetBrightness(BacklightKind::Keyboard, scale.value() as u32));
+                                        } @keyboard_brightness_changed,
+                                    },
+                                },
                             },
-                        },
 
-                        // Connectivity Section
-                        adw::PreferencesGroup {
-                            set_title: "Connectivity",
+                            // Power Section
+                            adw::PreferencesGroup {
+                                set_title: "Power",
+                                #[watch]
+                                set_visible: model.power_profiles.is_some(),
 
-                            adw::ActionRow {
-                                set_title: "WiFi",
-                                set_subtitle: &model.wifi_status._status,
-                                add_suffix = &gtk::Switch {
-                                    set_valign: gtk::Align::Center,
+                                adw::ActionRow {
+                                    set_title: "Power Mode",
                                     #[watch]
-                                    set_active: model.wifi_status.enabled,
-                                    connect_state_set[sender] => move |_, enabled| {
-                                        sender.input(ConfMessage::ToggleWifi(enabled));
-                                        glib::Propagation::Proceed
-                                    }
+                                    set_subtitle: &model.power_profiles.as_ref().map(PowerProfiles::subtitle).unwrap_or_default(),
+                                    add_suffix = &gtk::Box {
+                                        add_css_class: "linked",
+                                        set_valign: gtk::Align::Center,
+
+                                        #[name = "power_saver_button"]
+                                        gtk::ToggleButton {
+                                            set_icon_name: "power-profile-power-saver-symbolic",
+                                            set_tooltip_text: Some("Power Saver"),
+                                            #[watch]
+                                            set_visible: model.has_power_profile("power-saver"),
+                                            #[watch]
+                                            #[block_signal(power_saver_toggled)]
+                                            set_active: model.is_power_profile("power-saver"),
+                                            connect_toggled[sender] => move |button| {
+                                                if button.is_active() {
+                                                    sender.input(ConfMessage::SetPowerProfile("power-saver".to_string()));
+                                                }
+                                            } @power_saver_toggled,
+                                        },
+                                        gtk::ToggleButton {
+                                            set_icon_name: "power-profile-balanced-symbolic",
+                                            set_tooltip_text: Some("Balanced"),
+                                            set_group: Some(&power_saver_button),
+                                            #[watch]
+                                            set_visible: model.has_power_profile("balanced"),
+                                            #[watch]
+                                            #[block_signal(balanced_toggled)]
+                                            set_active: model.is_power_profile("balanced"),
+                                            connect_toggled[sender] => move |button| {
+                                                if button.is_active() {
+                                                    sender.input(ConfMessage::SetPowerProfile("balanced".to_string()));
+                                                }
+                                            } @balanced_toggled,
+                                        },
+                                        gtk::ToggleButton {
+                                            set_icon_name: "power-profile-performance-symbolic",
+                                            set_tooltip_text: Some("Performance"),
+                                            set_group: Some(&power_saver_button),
+                                            #[watch]
+                                            set_visible: model.has_power_profile("performance"),
+                                            #[watch]
+                                            #[block_signal(performance_toggled)]
+                                            set_active: model.is_power_profile("performance"),
+                                            connect_toggled[sender] => move |button| {
+                                                if button.is_active() {
+                                                    sender.input(ConfMessage::SetPowerProfile("performance".to_string()));
+                                                }
+                                            } @performance_toggled,
+                                        },
+                                    },
+                                },
+                            },
+
+                            // Sound Section
+                            adw::PreferencesGroup {
+                                set_title: "Sound",
+
+                                #[local_ref]
+                                output_row -> adw::ComboRow {
+                                    set_title: "Output",
+                                },
+
+                                adw::ActionRow {
+                                    add_prefix = &gtk::Button {
+                                        add_css_class: "flat",
+                                        set_valign: gtk::Align::Center,
+                                        set_tooltip_text: Some("Mute"),
+                                        #[watch]
+                                        set_icon_name: volume_icon(model.default_device(DeviceKind::Output)),
+                                        connect_clicked[sender] => move |_| {
+                                            sender.input(ConfMessage::ToggleMute(DeviceKind::Output));
+                                        },
+                                    },
+                                    add_suffix = &gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 1.0) {
+                                        set_hexpand: true,
+                                        set_width_request: 260,
+                                        set_valign: gtk::Align::Center,
+                                        #[watch]
+                                        #[block_signal(output_volume_changed)]
+                                        set_value: model.default_volume(DeviceKind::Output),
+                                        connect_value_changed[sender] => move |scale| {
+                                            sender.input(ConfMessage::SetVolume(DeviceKind::Output, scale.value() as u32));
+                                        } @output_volume_changed,
+                                    },
+                                },
+
+                                #[local_ref]
+                                input_row -> adw::ComboRow {
+                                    set_title: "Input",
+                                },
+
+                                adw::ActionRow {
+                                    add_prefix = &gtk::Button {
+                                        add_css_class: "flat",
+                                        set_valign: gtk::Align::Center,
+                                        set_tooltip_text: Some("Mute"),
+                                        #[watch]
+                                        set_icon_name: microphone_icon(model.default_device(DeviceKind::Input)),
+                                        connect_clicked[sender] => move |_| {
+                                            sender.input(ConfMessage::ToggleMute(DeviceKind::Input));
+                                        },
+                                    },
+                                    add_suffix = &gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 1.0) {
+                                        set_hexpand: true,
+                                        set_width_request: 260,
+                                        set_valign: gtk::Align::Center,
+                                        #[watch]
+                                        #[block_signal(input_volume_changed)]
+                                        set_value: model.default_volume(DeviceKind::Input),
+                                        connect_value_changed[sender] => move |scale| {
+                                            sender.input(ConfMessage::SetVolume(DeviceKind::Input, scale.value() as u32));
+                                        } @input_volume_changed,
+                                    },
+                                },
+                            },
+
+                            // Media Section
+                            adw::PreferencesGroup {
+                                set_title: "Media",
+                                #[watch]
+                                set_visible: model.media.is_some(),
+
+                                #[local_ref]
+                                player_row -> adw::ComboRow {
+                                    set_title: "Player",
+                                    #[watch]
+                                    set_visible: model.players.len() > 1,
+                                },
+
+                                gtk::Box {
+                                    set_orientation: gtk::Orientation::Vertical,
+                                    set_spacing: 6,
+                                    set_margin_top: 12,
+                                    add_css_class: "card",
+                                    add_css_class: "media-card",
+
+                                    gtk::Box {
+                                        set_spacing: 12,
+
+                                        #[local_ref]
+                                        media_art -> gtk::Image {
+                                            set_pixel_size: 64,
+                                            add_css_class: "media-art",
+                                        },
+
+                                        gtk::Box {
+                                            set_orientation: gtk::Orientation::Vertical,
+                                            set_valign: gtk::Align::Center,
+                                            set_hexpand: true,
+                                            gtk::Label {
+                                                #[watch]
+                                                set_label: model.media.as_ref().map_or("", |player| player.title.as_str()),
+                                                add_css_class: "heading",
+                                                set_xalign: 0.0,
+                                                set_ellipsize: gtk::pango::EllipsizeMode::End,
+                                            },
+                                            gtk::Label {
+                                                #[watch]
+                                                set_label: model.media.as_ref().map_or("", |player| player.artist.as_str()),
+                                                add_css_class: "dim-label",
+                                                set_xalign: 0.0,
+                                                set_ellipsize: gtk::pango::EllipsizeMode::End,
+                                            },
+                                        },
+                                    },
+
+                                    gtk::Box {
+                                        set_spacing: 6,
+                                        #[watch]
+                                        set_visible: model.media.as_ref().is_some_and(|player| player.can_seek && player.length > 0),
+                                        gtk::Label {
+                                            #[watch]
+                                            set_label: &model.media.as_ref().map(|player| format_time(player.position)).unwrap_or_default(),
+                                            add_css_class: "dim-label",
+                                            add_css_class: "numeric",
+                                        },
+                                        gtk::Scale {
+                                            set_hexpand: true,
+                                            #[watch]
+                                            #[block_signal(seek_changed)]
+                                            set_range: (0.0, model.media.as_ref().map_or(0, |player| player.length) as f64 / 1e6),
+                                            #[watch]
+                                            #[block_signal(seek_changed)]
+                                            set_value: model.media.as_ref().map_or(0, |player| player.position) as f64 / 1e6,
+                                            connect_value_changed[sender] => move |scale| {
+                                                sender.input(ConfMessage::SeekMedia(scale.value()));
+                                            } @seek_changed,
+                                        },
+                                        gtk::Label {
+                                            #[watch]
+                                            set_label: &model.media.as_ref().map(|player| format_time(player.length)).unwrap_or_default(),
+                                            add_css_class: "dim-label",
+                                            add_css_class: "numeric",
+                                        },
+                                    },
+
+                                    gtk::Box {
+                                        set_halign: gtk::Align::Center,
+                                        set_spacing: 12,
+                                        gtk::Button {
+                                            set_icon_name: "media-skip-backward-symbolic",
+                                            add_css_class: "circular",
+                                            connect_clicked[sender] => move |_| {
+                                                sender.input(ConfMessage::MediaPrevious);
+                                            },
+                                        },
+                                        gtk::Button {
+                                            #[watch]
+                                            set_icon_name: if model.media.as_ref().is_some_and(Player::is_playing) {
+                                                "media-playback-pause-symbolic"
+                                            } else {
+                                                "media-playback-start-symbolic"
+                                            },
+                                            add_css_class: "circular",
+                                            add_css_class: "suggested-action",
+                                            connect_clicked[sender] => move |_| {
+                                                sender.input(ConfMessage::MediaPlayPause);
+                                            },
+                                        },
+                                        gtk::Button {
+                                            set_icon_name: "media-skip-forward-symbolic",
+                                            add_css_class: "circular",
+                                            connect_clicked[sender] => move |_| {
+                                                sender.input(ConfMessage::MediaNext);
+                                            },
+                                        },
+                                    },
+                                },
+                            },
+
+                            // Connectivity Section
+                            adw::PreferencesGroup {
+                                set_title: "Connectivity",
+
+                                adw::ActionRow {
+                                    set_title: "Airplane Mode",
+                                    #[watch]
+                                    set_subtitle: &describe_radios(&model.radios),
+                                    add_suffix = &gtk::Switch {
+                                        set_valign: gtk::Align::Center,
+                                        #[watch]
+                                        #[block_signal(airplane_toggled)]
+                                        set_active: is_airplane_mode(&model.radios),
+                                        connect_state_set[sender] => move |_, enabled| {
+                                            sender.input(ConfMessage::ToggleAirplaneMode(enabled));
+                                            glib::Propagation::Proceed
+                                        } @airplane_toggled,
+                                    },
+                                },
+
+                                adw::ActionRow {
+                                    set_title: "WiFi",
+                                    #[watch]
+                                    set_subtitle: model.radio_subtitle(&model.wifi_status, "wlan"),
+                                    add_suffix = &gtk::Switch {
+                                        set_valign: gtk::Align::Center,
+                                        #[watch]
+                                        set_sensitive: !radio_state(&model.radios, "wlan").hard_blocked,
+                                        #[watch]
+                                        #[block_signal(wifi_toggled)]
+                                        set_active: model.wifi_status.enabled,
+                                        connect_state_set[sender] => move |_, enabled| {
+                                            sender.input(ConfMessage::ToggleWifi(enabled));
+                                            glib::Propagation::Proceed
+                                        } @wifi_toggled,
+                                    },
+                                },
+
+                                adw::ActionRow {
+                                    set_title: "Bluetooth",
+                                    #[watch]
+                                    set_subtitle: model.radio_subtitle(&model.bluetooth_status, "bluetooth"),
+                                    add_suffix = &gtk::Switch {
+                                        set_valign: gtk::Align::Center,
+                                        #[watch]
+                                        set_sensitive: !radio_state(&model.radios, "bluetooth").hard_blocked,
+                                        #[watch]
+                                        #[block_signal(bluetooth_toggled)]
+                                        set_active: model.bluetooth_status.enabled,
+                                        connect_state_set[sender] => move |_, enabled| {
+                                            sender.input(ConfMessage::ToggleBluetooth(enabled));
+                                            glib::Propagation::Proceed
+                                        } @bluetooth_toggled,
+                                    },
+                                },
+
+                                #[local_ref]
+                                adapter_row -> adw::ComboRow {
+                                    set_title: "Adapter",
+                                    #[watch]
+                                    set_visible: model.bluetooth_adapters.len() > 1,
                                 },
                             },
 
-                            adw::ActionRow {
-                                set_title: "Bluetooth",
-                                set_subtitle: &model.bluetooth_status._status,
-                                add_suffix = &gtk::Switch {
+                            // Bluetooth Devices Section
+                            adw::PreferencesGroup {
+                                set_title: "Bluetooth Devices",
+                                #[wrap(Some)]
+                                set_header_suffix = &gtk::Switch {
                                     set_valign: gtk::Align::Center,
+                                    set_tooltip_text: Some("Search for devices"),
                                     #[watch]
-                                    set_active: model.bluetooth_status.enabled,
+                                    #[block_signal(discovery_toggled)]
+                                    set_active: model.bluetooth_discovering,
                                     connect_state_set[sender] => move |_, enabled| {
-                                        sender.input(ConfMessage::ToggleBluetooth(enabled));
+                                        sender.input(ConfMessage::ToggleDiscovery(enabled));
                                         glib::Propagation::Proceed
-                                    }
+                                    } @discovery_toggled,
+                                },
+
+                                #[local_ref]
+                                bluetooth_list -> gtk::ListBox {
+                                    add_css_class: "boxed-list",
+                                    set_selection_mode: gtk::SelectionMode::None,
                                 },
                             },
-                        },
+
+                            // Networks Section
+                            adw::PreferencesGroup {
+                                set_title: "Networks",
+
+                                #[local_ref]
+                                networks_list -> gtk::ListBox {
+                                    add_css_class: "boxed-list",
+                                    set_selection_mode: gtk::SelectionMode::None,
+                                },
+                            },
+                        }
                     }
                 }
             }
@@ -110,10 +585,15 @@ impl SimpleComponent for ConfigWindow {
     }
 
     fn init(
-        _: Self::Init,
+        init: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        let ConfigWindowInit {
+            config,
+            audio,
+            players,
+        } = init;
         // Initialize layer shell
         root.init_layer_shell();
         root.set_layer(Layer::Overlay);
@@ -128,17 +608,116 @@ impl SimpleComponent for ConfigWindow {
             relm4::gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
         );
 
-        let model = Self {
+        let mut model = Self {
+            config,
             wifi_status: read_wifi_status(),
             bluetooth_status: read_bluetooth_status(),
+            radios: read_radios(),
             cpu_usage: read_cpu_usage(),
             gpu_usage: read_gpu_usage(),
+            ethernet_devices: read_ethernet_devices(),
+            vpn_connections: read_vpn_connections(),
+            networks_list: gtk::ListBox::new(),
+            bluetooth_adapters: Vec::new(),
+            adapter_row: adw::ComboRow::new(),
+            adapter_row_handler: None,
+            bluetooth_devices: read_devices(),
+            bluetooth_busy: HashSet::new(),
+            bluetooth_discovering: read_discovering(),
+            bluetooth_list: gtk::ListBox::new(),
+            bluetooth_session: BluetoothSession::new(sender.input_sender().clone())
+                .inspect_err(|e| eprintln!("Failed to start Bluetooth agent: {}", e))
+                .ok(),
+            pairing_dialog: None,
+            window: root.clone(),
+            toast_overlay: adw::ToastOverlay::new(),
+            power_profiles: read_power_profiles(),
+            screen_backlight: read_backlight(BacklightKind::Screen),
+            keyboard_backlight: read_backlight(BacklightKind::Keyboard),
+            audio,
+            outputs: Vec::new(),
+            inputs: Vec::new(),
+            output_row: adw::ComboRow::new(),
+            input_row: adw::ComboRow::new(),
+            output_row_handler: None,
+            input_row_handler: None,
+            notifications: Vec::new(),
+            notifications_list: gtk::ListBox::new(),
+            dnd: None,
+            players: Vec::new(),
+            players_watcher: players,
+            media: None,
+            player_row: adw::ComboRow::new(),
+            player_row_handler: None,
+            media_art: gtk::Image::new(),
         };
 
+        let networks_list = &model.networks_list;
+        let notifications_list = &model.notifications_list;
+        let bluetooth_list = &model.bluetooth_list;
+        let adapter_row = &model.adapter_row;
+        let toast_overlay = &model.toast_overlay;
+        let session_box = &gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        for action in SessionAction::ALL {
+            if !action.is_available() {
+                continue;
+            }
+            let button = gtk::Button::builder()
+                .icon_name(action.icon())
+                .tooltip_text(action.label())
+                .halign(gtk::Align::Center)
+                .build();
+            button.add_css_class("circular");
+            let sender = sender.clone();
+            button.connect_clicked(move |_| {
+                sender.input(ConfMessage::RequestSessionAction(action));
+            });
+            session_box.append(&button);
+        }
+        let output_row = &model.output_row;
+        let input_row = &model.input_row;
+        let player_row = &model.player_row;
+        let media_art = &model.media_art;
         let widgets = view_output!();
 
-        // Start hidden
+        model.update_adapters();
+        model.adapter_row_handler = Some(model.adapter_row.connect_selected_notify({
+            let sender = sender.clone();
+            move |row| sender.input(ConfMessage::SelectAdapter(row.selected()))
+        }));
+        for kind in [DeviceKind::Output, DeviceKind::Input] {
+            let sender = sender.clone();
+            let handler = model.device_row(kind).connect_selected_notify(move |row| {
+                sender.input(ConfMessage::SelectAudioDevice(kind, row.selected()));
+            });
+            match kind {
+                DeviceKind::Output => model.output_row_handler = Some(handler),
+                DeviceKind::Input => model.input_row_handler = Some(handler),
+            }
+        }
+        model.player_row_handler = Some(model.player_row.connect_selected_notify({
+            let sender = sender.clone();
+            move |row| sender.input(ConfMessage::SelectPlayer(row.selected()))
+        }));
+        watch_adapters(model.bluetooth_session.as_ref(), {
+            let sender = sender.clone();
+            move || sender.input(ConfMessage::AdaptersChanged)
+        });
+
+        model.rebuild_networks_list(&sender);
+        model.rebuild_bluetooth_list(&sender);
+        model.rebuild_notifications_list(&sender);
+
+        // Start hidden, and catch up as soon as shown since nothing is read while hidden
         root.set_visible(false);
+        root.connect_visible_notify({
+            let sender = sender.clone();
+            move |window| {
+                if window.is_visible() {
+                    sender.input(ConfMessage::UpdateStatus);
+                }
+            }
+        });
 
         // Setup periodic updates
         let sender_clone = sender.clone();
@@ -150,40 +729,708 @@ impl SimpleComponent for ConfigWindow {
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
             ConfMessage::UpdateStatus => {
+                if !self.window.is_visible() {
+                    return;
+                }
                 self.wifi_status = read_wifi_status();
                 self.bluetooth_status = read_bluetooth_status();
+                self.radios = read_radios();
                 self.cpu_usage = read_cpu_usage();
                 self.gpu_usage = read_gpu_usage();
+                self.update_networks(&sender);
+                self.update_bluetooth_devices(&sender);
+                self.screen_backlight = read_backlight(BacklightKind::Screen);
+                self.keyboard_backlight = read_backlight(BacklightKind::Keyboard);
+                self.power_profiles = read_power_profiles();
             }
             ConfMessage::ToggleWifi(enabled) => {
-                if let Ok(conn) = Connection::system() {
-                    if let Ok(proxy) = zbus::blocking::Proxy::new(
-                        &conn,
-                        "org.freedesktop.NetworkManager",
-                        "/org/freedesktop/NetworkManager",
-                        "org.freedesktop.NetworkManager",
-                    ) {
-                        let _ = proxy.set_property("WirelessEnabled", enabled);
-                    }
+                if let Err(e) = set_wifi_enabled(enabled) {
+                    self.show_error("Could not switch WiFi", &e);
                 }
                 self.wifi_status = read_wifi_status();
             }
             ConfMessage::ToggleBluetooth(enabled) => {
-                if let Ok(conn) = Connection::system() {
-                    if let Ok(proxy) = zbus::blocking::Proxy::new(
-                        &conn,
-                        "org.bluez",
-                        "/org/bluez/hci0",
-                        "org.bluez.Adapter1",
-                    ) {
-                        let _ = proxy.set_property("Powered", enabled);
-                    }
+                if let Err(e) = set_bluetooth_powered(enabled) {
+                    self.show_error("Could not switch Bluetooth", &e);
                 }
                 self.bluetooth_status = read_bluetooth_status();
             }
+            ConfMessage::ToggleAirplaneMode(enabled) => {
+                if is_airplane_mode(&self.radios) != enabled
+                    && let Err(e) = set_airplane_mode(enabled)
+                {
+                    self.show_error("Could not switch airplane mode", &e.into());
+                }
+                self.radios = read_radios();
+                self.wifi_status = read_wifi_status();
+                self.bluetooth_status = read_bluetooth_status();
+            }
+            ConfMessage::AdaptersChanged => {
+                self.update_adapters();
+                self.bluetooth_status = read_bluetooth_status();
+                self.update_bluetooth_devices(&sender);
+            }
+            ConfMessage::SelectAdapter(index) => {
+                if let Some(adapter) = self.bluetooth_adapters.get(index as usize) {
+                    select_adapter(&adapter.path);
+                }
+                self.bluetooth_status = read_bluetooth_status();
+                self.update_bluetooth_devices(&sender);
+            }
+            ConfMessage::SetVolume(kind, volume) => {
+                if let Some(device) = self.default_device(kind).cloned() {
+                    self.change_audio(&sender, "Could not change the volume".to_string(), {
+                        move |backend| change_volume(backend, kind, &device, volume)
+                    });
+                }
+            }
+            ConfMessage::ToggleMute(kind) => {
+                if let Some(device) = self.default_device(kind).cloned() {
+                    self.change_audio(&sender, "Could not mute".to_string(), {
+                        move |backend| toggle_mute(backend, kind, &device)
+                    });
+                }
+            }
+            ConfMessage::SelectAudioDevice(kind, index) => {
+                let devices = match kind {
+                    DeviceKind::Output => &self.outputs,
+                    DeviceKind::Input => &self.inputs,
+                };
+                if let Some(device) = devices.get(index as usize).cloned() {
+                    let failure = format!("Could not switch to {}", device.description);
+                    self.change_audio(&sender, failure, {
+                        move |backend| make_default(backend, kind, &device)
+                    });
+                }
+            }
+            ConfMessage::AudioChanged(devices) => self.update_audio(devices),
+            ConfMessage::AudioChangeFailed(failure, e) => self.show_error(&failure, &e),
+            ConfMessage::RequestSessionAction(action) => {
+                if action.needs_confirmation() {
+                    self.confirm_session_action(action, &sender);
+                } else {
+                    sender.input(ConfMessage::PerformSessionAction(action));
+                }
+            }
+            ConfMessage::PerformSessionAction(action) => {
+                self.window.set_visible(false);
+                // polkit may ask for a password first, the bar has to keep drawing meanwhile
+                let config = self.config.session.clone();
+                let thread_sender = sender.clone();
+                std::thread::spawn(move || {
+                    let result = action.perform(&config);
+                    thread_sender.input(ConfMessage::SessionActionDone(action, result));
+                });
+            }
+            ConfMessage::SessionActionDone(action, result) => {
+                if let Err(e) = result {
+                    self.window.set_visible(true);
+                    self.show_error(&format!("Could not {}", action.label().to_lowercase()), &e);
+                }
+            }
+            ConfMessage::SetPowerProfile(profile) => {
+                if !self.is_power_profile(&profile)
+                    && let Err(e) = set_power_profile(&profile)
+                {
+                    self.show_error("Could not change power mode", &e);
+                }
+                self.power_profiles = read_power_profiles();
+            }
+            ConfMessage::PlayersChanged(players) => self.update_media(players),
+            ConfMessage::SelectPlayer(index) => {
+                if let Some(player) = self.players.get(index as usize) {
+                    select_player(&player.bus_name);
+                }
+                self.players_watcher.refresh();
+            }
+            ConfMessage::MediaControlDone(failure, result) => {
+                if let Err(e) = result {
+                    self.show_error(failure, &e);
+                }
+                self.players_watcher.refresh();
+            }
+            ConfMessage::MediaPrevious => {
+                self.control_media(&sender, "Could not go back", mpris::previous);
+            }
+            ConfMessage::MediaPlayPause => {
+                self.control_media(&sender, "Could not play or pause", mpris::play_pause);
+            }
+            ConfMessage::MediaNext => {
+                self.control_media(&sender, "Could not skip", mpris::next);
+            }
+            ConfMessage::SeekMedia(seconds) => {
+                let position = (seconds * 1e6) as i64;
+                // The slider also moves while the track plays on its own
+                if self
+                    .media
+                    .as_ref()
+                    .is_some_and(|player| (position - player.position).abs() > 1_000_000)
+                {
+                    self.control_media(&sender, "Could not seek", move |player| {
+                        mpris::seek(player, position)
+                    });
+                }
+            }
+            ConfMessage::SetBrightness(kind, percent) => {
+                let backlight = match kind {
+                    BacklightKind::Screen => &self.screen_backlight,
+                    BacklightKind::Keyboard => &self.keyboard_backlight,
+                };
+                if let Some(current) = backlight
+                    && current.percent() != percent
+                    && let Err(e) = set_brightness(current, percent)
+                {
+                    self.show_error("Could not change the brightness", &e.into());
+                }
+                match kind {
+                    BacklightKind::Screen => self.screen_backlight = read_backlight(kind),
+                    BacklightKind::Keyboard => self.keyboard_backlight = read_backlight(kind),
+                }
+            }
+            ConfMessage::NetworksChanged => {
+                if self.window.is_visible() {
+                    self.update_networks(&sender);
+                }
+            }
+            ConfMessage::ToggleVpn(path, active) => {
+                if let Some(vpn) = self.vpn_connections.iter().find(|vpn| vpn.path == path)
+                    && let Err(e) = set_vpn_active(vpn, active)
+                {
+                    self.show_error(&format!("Could not switch {}", vpn.id), &e.into());
+                    // The switch already flipped, put it back even though nothing changed
+                    self.ethernet_devices = read_ethernet_devices();
+                    self.vpn_connections = read_vpn_connections();
+                    self.rebuild_networks_list(&sender);
+                } else {
+                    self.update_networks(&sender);
+                }
+            }
+            ConfMessage::ToggleDiscovery(enabled) => {
+                if let Some(session) = &self.bluetooth_session
+                    && let Err(e) = session.set_discovering(enabled)
+                {
+                    self.show_error("Could not search for devices", &e.into());
+                }
+                self.update_bluetooth_devices(&sender);
+            }
+            ConfMessage::ToggleBluetoothDevice(path) => {
+                let Some(device) = self.bluetooth_devices.iter().find(|d| d.path == path) else {
+                    return;
+                };
+                if !self.bluetooth_busy.insert(path.clone()) {
+                    return;
+                }
+                let action = DeviceAction::for_device(device);
+                let thread_sender = sender.clone();
+                std::thread::spawn(move || {
+                    let result = action.perform(&path).map_err(ToggleError::from);
+                    thread_sender.input(ConfMessage::BluetoothDeviceDone(path, action, result));
+                });
+                self.rebuild_bluetooth_list(&sender);
+            }
+            ConfMessage::BluetoothDeviceDone(path, action, result) => {
+                if let Err(e) = result {
+                    let name = self.bluetooth_device_name(&path);
+                    self.show_error(&format!("Could not {} {}", action.verb(), name), &e);
+                }
+                self.bluetooth_busy.remove(&path);
+                self.bluetooth_devices = read_devices();
+                self.rebuild_bluetooth_list(&sender);
+            }
+            ConfMessage::BluetoothConfirm {
+                device,
+                request,
+                reply,
+            } => {
+                let name = self.bluetooth_device_name(&device);
+                let (heading, body, accept) = match request {
+                    AgentRequest::Pair(Some(passkey)) => (
+                        "Pair Device",
+                        format!("Confirm that {} shows the passkey {:06}.", name, passkey),
+                        "Pair",
+                    ),
+                    AgentRequest::Pair(None) => (
+                        "Pair Device",
+                        format!("Allow {} to pair with this computer?", name),
+                        "Pair",
+                    ),
+                    AgentRequest::Service(uuid) => (
+                        "Bluetooth Access",
+                        format!("Allow {} to use {}?", name, service_name(&uuid)),
+                        "Allow",
+                    ),
+                };
+                let dialog = adw::AlertDialog::new(Some(heading), Some(&body));
+                dialog.add_responses(&[("cancel", "Cancel"), ("accept", accept)]);
+                dialog.set_response_appearance("accept", adw::ResponseAppearance::Suggested);
+                dialog.set_close_response("cancel");
+                dialog.connect_response(None, move |_, response| {
+                    reply.emit(response == "accept");
+                });
+                // Devices ask for services on their own, not only while pairing from here
+                self.window.set_visible(true);
+                dialog.present(Some(&self.window));
+                self.pairing_dialog = Some(dialog);
+            }
+            ConfMessage::BluetoothDisplayCode { device, code } => {
+                let name = self.bluetooth_device_name(&device);
+                let dialog = adw::AlertDialog::new(
+                    Some("Pair Device"),
+                    Some(&format!("Type {} on {} and press Enter.", code, name)),
+                );
+                dialog.add_response("close", "Close");
+                dialog.present(Some(&self.window));
+                self.pairing_dialog = Some(dialog);
+            }
+            ConfMessage::BluetoothAgentCancel => {
+                if let Some(dialog) = self.pairing_dialog.take() {
+                    dialog.force_close();
+                }
+            }
+            ConfMessage::NotificationReceived(notification) => {
+                self.notifications.retain(|n| n.id != notification.id);
+                self.notifications.insert(0, notification);
+                self.rebuild_notifications_list(&sender);
+            }
+            ConfMessage::RemoveNotification(id) => {
+                let count = self.notifications.len();
+                self.notifications.retain(|n| n.id != id);
+                if self.notifications.len() != count {
+                    self.rebuild_notifications_list(&sender);
+                }
+            }
+            ConfMessage::DoNotDisturbChanged(dnd) => self.dnd = dnd,
+            ConfMessage::ClearNotifications => {
+                for notification in self.notifications.drain(..) {
+                    sender
+                        .output(ConfOutput::DismissNotification(notification.id))
+                        .ok();
+                }
+                self.rebuild_notifications_list(&sender);
+            }
+        }
+    }
+}
+
+impl ConfigWindow {
+    /// Asks before a session action and runs it when the countdown runs out
+    fn confirm_session_action(&self, action: SessionAction, sender: &ComponentSender<Self>) {
+        let timeout = self.config.session.confirm_timeout;
+        let dialog = adw::AlertDialog::new(
+            Some(&format!("{}?", action.label())),
+            Some(&action.countdown_message(timeout)),
+        );
+        dialog.add_responses(&[("cancel", "Cancel"), ("confirm", action.label())]);
+        dialog.set_response_appearance("confirm", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        // Set once the dialog is answered so the countdown stops
+        let done = Rc::new(Cell::new(false));
+        dialog.connect_response(None, {
+            let done = done.clone();
+            let sender = sender.clone();
+            move |_, response| {
+                if done.replace(true) {
+                    return;
+                }
+                if response == "confirm" {
+                    sender.input(ConfMessage::PerformSessionAction(action));
+                }
+            }
+        });
+
+        let remaining = Cell::new(timeout);
+        glib::timeout_add_seconds_local(1, {
+            let dialog = dialog.clone();
+            let sender = sender.clone();
+            move || {
+                if done.get() {
+                    return glib::ControlFlow::Break;
+                }
+                let seconds = remaining.get().saturating_sub(1);
+                remaining.set(seconds);
+                if seconds == 0 {
+                    done.set(true);
+                    dialog.force_close();
+                    sender.input(ConfMessage::PerformSessionAction(action));
+                    return glib::ControlFlow::Break;
+                }
+                dialog.set_body(&action.countdown_message(seconds));
+                glib::ControlFlow::Continue
+            }
+        });
+
+        dialog.present(Some(&self.window));
+    }
+
+    fn has_power_profile(&self, profile: &str) -> bool {
+        self.power_profiles
+            .as_ref()
+            .is_some_and(|profiles| profiles.has(profile))
+    }
+
+    fn is_power_profile(&self, profile: &str) -> bool {
+        self.power_profiles
+            .as_ref()
+            .is_some_and(|profiles| profiles.active == profile)
+    }
+
+    fn default_device(&self, kind: DeviceKind) -> Option<&AudioDevice> {
+        match kind {
+            DeviceKind::Output => &self.outputs,
+            DeviceKind::Input => &self.inputs,
+        }
+        .iter()
+        .find(|device| device.is_default)
+    }
+
+    fn default_volume(&self, kind: DeviceKind) -> f64 {
+        self.default_device(kind)
+            .map_or(0.0, |device| device.volume as f64)
+    }
+
+    fn device_row(&self, kind: DeviceKind) -> &adw::ComboRow {
+        match kind {
+            DeviceKind::Output => &self.output_row,
+            DeviceKind::Input => &self.input_row,
+        }
+    }
+
+    /// Changes a sound device on the audio thread, which reports the devices back afterwards
+    fn change_audio(
+        &self,
+        sender: &ComponentSender<Self>,
+        failure: String,
+        change: impl FnOnce(&dyn AudioBackend) -> std::io::Result<()> + Send + 'static,
+    ) {
+        let sender = sender.clone();
+        self.audio.change(change, move |e| {
+            sender.input(ConfMessage::AudioChangeFailed(failure, e.into()));
+        });
+    }
+
+    /// Shows the sound devices read by the bar and syncs the device selectors without
+    /// triggering them
+    fn update_audio(&mut self, devices: AudioDevices) {
+        for kind in [DeviceKind::Output, DeviceKind::Input] {
+            let devices = devices.of_kind(kind).to_vec();
+            let (current, handler) = match kind {
+                DeviceKind::Output => (&mut self.outputs, &self.output_row_handler),
+                DeviceKind::Input => (&mut self.inputs, &self.input_row_handler),
+            };
+            let row = match kind {
+                DeviceKind::Output => &self.output_row,
+                DeviceKind::Input => &self.input_row,
+            };
+
+            if let Some(handler) = handler {
+                row.block_signal(handler);
+            }
+            let names_changed = devices.len() != current.len()
+                || devices
+                    .iter()
+                    .zip(current.iter())
+                    .any(|(a, b)| a.name != b.name || a.description != b.description);
+            if names_changed {
+                let descriptions: Vec<&str> = devices
+                    .iter()
+                    .map(|device| device.description.as_str())
+                    .collect();
+                row.set_model(Some(&gtk::StringList::new(&descriptions)));
+            }
+            if let Some(index) = devices.iter().position(|device| device.is_default) {
+                row.set_selected(index as u32);
+            }
+            if let Some(handler) = handler {
+                row.unblock_signal(handler);
+            }
+            *current = devices;
+        }
+    }
+
+    /// Sends a command to the shown player on a thread, players may be slow to answer
+    fn control_media(
+        &self,
+        sender: &ComponentSender<Self>,
+        failure: &'static str,
+        control: impl FnOnce(&Player) -> zbus::Result<()> + Send + 'static,
+    ) {
+        let Some(player) = self.media.clone() else {
+            return;
+        };
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            let result = control(&player).map_err(ToggleError::from);
+            sender.input(ConfMessage::MediaControlDone(failure, result));
+        });
+    }
+
+    /// Shows the players read by the bar, syncing the player selector and album art
+    fn update_media(&mut self, players: Vec<Player>) {
+        let media = active_player(&players).cloned();
+
+        if let Some(handler) = &self.player_row_handler {
+            self.player_row.block_signal(handler);
+        }
+        let names_changed = players.len() != self.players.len()
+            || players
+                .iter()
+                .zip(self.players.iter())
+                .any(|(a, b)| a.bus_name != b.bus_name);
+        if names_changed {
+            let names: Vec<&str> = players
+                .iter()
+                .map(|player| player.identity.as_str())
+                .collect();
+            self.player_row
+                .set_model(Some(&gtk::StringList::new(&names)));
+        }
+        if let Some(index) = media
+            .as_ref()
+            .and_then(|media| players.iter().position(|p| p.bus_name == media.bus_name))
+        {
+            self.player_row.set_selected(index as u32);
+        }
+        if let Some(handler) = &self.player_row_handler {
+            self.player_row.unblock_signal(handler);
+        }
+
+        let art = media.as_ref().and_then(Player::art_path);
+        if art != self.media.as_ref().and_then(Player::art_path) || self.media.is_none() {
+            match art {
+                Some(path) => self.media_art.set_from_file(Some(&path)),
+                None => self
+                    .media_art
+                    .set_icon_name(Some("audio-x-generic-symbolic")),
+            }
+        }
+        self.players = players;
+        self.media = media;
+    }
+
+    /// Reports a failed toggle with the D-Bus error name and message
+    fn show_error(&self, action: &str, error: &ToggleError) {
+        self.toast_overlay
+            .add_toast(adw::Toast::new(&format!("{}: {}", action, error)));
+    }
+
+    /// Explains a disabled radio when its hardware kill switch is engaged
+    fn radio_subtitle<'a>(&self, status: &'a ConnectivityStatus, kind: &str) -> &'a str {
+        if radio_state(&self.radios, kind).hard_blocked {
+            "Disabled by hardware switch"
+        } else {
+            &status._status
+        }
+    }
+
+    /// Refreshes the adapter choices and selects the adapter currently in use
+    fn update_adapters(&mut self) {
+        // Errors show up in the Bluetooth row's subtitle instead
+        self.bluetooth_adapters = read_adapters().unwrap_or_default();
+        let names: Vec<&str> = self
+            .bluetooth_adapters
+            .iter()
+            .map(|adapter| adapter.name.as_str())
+            .collect();
+
+        if let Some(handler) = &self.adapter_row_handler {
+            self.adapter_row.block_signal(handler);
+        }
+        self.adapter_row
+            .set_model(Some(&gtk::StringList::new(&names)));
+        let current = choose_adapter(&self.bluetooth_adapters).map(|adapter| &adapter.path);
+        if let Some(index) = self
+            .bluetooth_adapters
+            .iter()
+            .position(|adapter| Some(&adapter.path) == current)
+        {
+            self.adapter_row.set_selected(index as u32);
+        }
+        if let Some(handler) = &self.adapter_row_handler {
+            self.adapter_row.unblock_signal(handler);
+        }
+    }
+
+    fn bluetooth_device_name(&self, path: &str) -> String {
+        self.bluetooth_devices
+            .iter()
+            .find(|device| device.path == path)
+            .map(|device| device.name.clone())
+            .unwrap_or_else(|| "the device".to_string())
+    }
+
+    /// Re-reads Bluetooth devices, rebuilding the list if anything changed
+    fn update_bluetooth_devices(&mut self, sender: &ComponentSender<Self>) {
+        let discovering = read_discovering();
+        let devices = read_devices();
+        // Discovery decides whether unpaired devices are listed at all
+        if devices != self.bluetooth_devices || discovering != self.bluetooth_discovering {
+            self.bluetooth_discovering = discovering;
+            self.bluetooth_devices = devices;
+            self.rebuild_bluetooth_list(sender);
+        }
+    }
+
+    fn rebuild_bluetooth_list(&self, sender: &ComponentSender<Self>) {
+        self.bluetooth_list.remove_all();
+
+        // Unpaired devices are only interesting while searching for new ones
+        let devices = self
+            .bluetooth_devices
+            .iter()
+            .filter(|device| device.paired || self.bluetooth_discovering);
+
+        for device in devices {
+            let busy = self.bluetooth_busy.contains(&device.path);
+            let subtitle = if busy {
+                if device.connected {
+                    "Disconnecting…".to_string()
+                } else if device.paired {
+                    "Connecting…".to_string()
+                } else {
+                    "Pairing…".to_string()
+                }
+            } else {
+                device.status()
+            };
+            let row = adw::ActionRow::builder()
+                .title(device.name.as_str())
+                .subtitle(subtitle.as_str())
+                .activatable(!busy)
+                .build();
+            row.add_prefix(&gtk::Image::from_icon_name(&device.icon));
+            let sender = sender.clone();
+            let path = device.path.clone();
+            row.connect_activated(move |_| {
+                sender.input(ConfMessage::ToggleBluetoothDevice(path.clone()));
+            });
+            self.bluetooth_list.append(&row);
+        }
+
+        if self.bluetooth_list.first_child().is_none() {
+            let row = adw::ActionRow::builder()
+                .title(if self.bluetooth_discovering {
+                    "Searching for devices…"
+                } else {
+                    "No paired devices"
+                })
+                .build();
+            self.bluetooth_list.append(&row);
+        }
+    }
+
+    /// Re-reads wired and VPN connections, rebuilding the list if anything changed
+    fn update_networks(&mut self, sender: &ComponentSender<Self>) {
+        let ethernet_devices = read_ethernet_devices();
+        let vpn_connections = read_vpn_connections();
+        if ethernet_devices != self.ethernet_devices || vpn_connections != self.vpn_connections {
+            self.ethernet_devices = ethernet_devices;
+            self.vpn_connections = vpn_connections;
+            self.rebuild_networks_list(sender);
+        }
+    }
+
+    fn rebuild_networks_list(&self, sender: &ComponentSender<Self>) {
+        self.networks_list.remove_all();
+
+        for device in &self.ethernet_devices {
+            let subtitle = match &device.ip {
+                Some(ip) => format!("{} · {}", device.status, ip),
+                None => device.status.clone(),
+            };
+            let row = adw::ActionRow::builder()
+                .title(device.interface.as_str())
+                .subtitle(subtitle.as_str())
+                .build();
+            row.add_prefix(&gtk::Image::from_icon_name(if device.connected {
+                "network-wired-symbolic"
+            } else {
+                "network-wired-disconnected-symbolic"
+            }));
+            self.networks_list.append(&row);
+        }
+
+        for vpn in &self.vpn_connections {
+            let row = adw::SwitchRow::builder()
+                .title(vpn.id.as_str())
+                .subtitle(if vpn.wireguard { "WireGuard" } else { "VPN" })
+                .active(vpn.active.is_some())
+                .build();
+            row.add_prefix(&gtk::Image::from_icon_name("network-vpn-symbolic"));
+            let sender = sender.clone();
+            let path = vpn.path.clone();
+            row.connect_active_notify(move |row| {
+                sender.input(ConfMessage::ToggleVpn(path.clone(), row.is_active()));
+            });
+            self.networks_list.append(&row);
+        }
+
+        if self.ethernet_devices.is_empty() && self.vpn_connections.is_empty() {
+            let row = adw::ActionRow::builder()
+                .title("No wired or VPN connections")
+                .build();
+            self.networks_list.append(&row);
+        }
+    }
+
+    /// Newest notifications first, each with a button removing it from the history
+    fn rebuild_notifications_list(&self, sender: &ComponentSender<Self>) {
+        self.notifications_list.remove_all();
+
+        for notification in &self.notifications {
+            let row = adw::ActionRow::builder()
+                .title(notification.summary.as_str())
+                .subtitle(notification.body.as_str())
+                .subtitle_lines(3)
+                .use_markup(false)
+                .build();
+            row.add_prefix(&notification.image());
+            let received = format!(
+                "{} · {}",
+                notification.app_name,
+                notification.received.format("%H:%M")
+            );
+            let time = gtk::Label::new(Some(&received));
+            time.add_css_class("dim-label");
+            row.add_suffix(&time);
+
+            let id = notification.id;
+            let dismiss = gtk::Button::from_icon_name("window-close-symbolic");
+            dismiss.add_css_class("flat");
+            dismiss.set_valign(gtk::Align::Center);
+            dismiss.set_tooltip_text(Some("Dismiss"));
+            dismiss.connect_clicked({
+                let sender = sender.clone();
+                move |_| {
+                    sender.input(ConfMessage::RemoveNotification(id));
+                    sender.output(ConfOutput::DismissNotification(id)).ok();
+                }
+            });
+            row.add_suffix(&dismiss);
+
+            if notification.actions.iter().any(|(key, _)| key == "default") {
+                row.set_activatable(true);
+                let sender = sender.clone();
+                row.connect_activated(move |_| {
+                    sender.input(ConfMessage::RemoveNotification(id));
+                    sender
+                        .output(ConfOutput::InvokeNotificationAction(
+                            id,
+                            "default".to_string(),
+                        ))
+                        .ok();
+                });
+            }
+            self.notifications_list.append(&row);
+        }
+
+        if self.notifications.is_empty() {
+            let row = adw::ActionRow::builder().title("No notifications").build();
+            self.notifications_list.append(&row);
         }
     }
 }
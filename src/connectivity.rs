@@ -1,7 +1,71 @@
+use crate::bluetooth::default_adapter;
+use std::fmt;
+use zbus::DBusError;
 use zbus::blocking::Connection;
 
+/// Why switching a radio or connection on or off failed
+#[derive(Debug)]
+pub enum ToggleError {
+    /// The bus or service could not be reached
+    Bus(zbus::Error),
+    /// The service refused the change, e.g. polkit denied it
+    DBus {
+        name: String,
+        message: String,
+    },
+    /// A device file or helper command failed, e.g. /dev/rfkill
+    Io(std::io::Error),
+    /// niri rejected an IPC request
+    Niri(String),
+    NoAdapter,
+}
+
+impl fmt::Display for ToggleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToggleError::Bus(e) => write!(f, "{}", e),
+            ToggleError::DBus { name, message } if message.is_empty() => write!(f, "{}", name),
+            ToggleError::DBus { name, message } => write!(f, "{}: {}", name, message),
+            ToggleError::Io(e) => write!(f, "{}", e),
+            ToggleError::Niri(e) => write!(f, "niri: {}", e),
+            ToggleError::NoAdapter => write!(f, "No Bluetooth adapter found"),
+        }
+    }
+}
+
+impl From<zbus::Error> for ToggleError {
+    fn from(e: zbus::Error) -> Self {
+        match e {
+            zbus::Error::MethodError(name, message, _) => ToggleError::DBus {
+                name: name.to_string(),
+                message: message.unwrap_or_default(),
+            },
+            zbus::Error::FDO(e) => (*e).into(),
+            e => ToggleError::Bus(e),
+        }
+    }
+}
+
+impl From<zbus::fdo::Error> for ToggleError {
+    fn from(e: zbus::fdo::Error) -> Self {
+        match e {
+            zbus::fdo::Error::ZBus(e) => e.into(),
+            e => ToggleError::DBus {
+                name: e.name().to_string(),
+                message: e.description().unwrap_or_default().to_string(),
+            },
+        }
+    }
+}
+
+impl From<std::io::Error> for ToggleError {
+    fn from(e: std::io::Error) -> Self {
+        ToggleError::Io(e)
+    }
+}
+
 /// WiFi and Bluetooth status
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ConnectivityStatus {
     pub enabled: bool,
     pub _status: String,
@@ -20,6 +84,12 @@ impl ConnectivityStatus {
     }
 }
 
+impl Default for ConnectivityStatus {
+    fn default() -> Self {
+        Self::unknown()
+    }
+}
+
 /// Checks WiFi status via NetworkManager DBus
 pub fn read_wifi_status() -> ConnectivityStatus {
     match Connection::system() {
@@ -31,21 +101,21 @@ pub fn read_wifi_status() -> ConnectivityStatus {
                 "org.freedesktop.NetworkManager",
             );
 
-            if let Ok(proxy) = proxy {
-                if let Ok(wifi_enabled) = proxy.get_property::<bool>("WirelessEnabled") {
-                    if let Ok(active_connections) = proxy
-                        .get_property::<Vec<zbus::zvariant::OwnedObjectPath>>("ActiveConnections")
-                    {
-                        if !active_connections.is_empty() && wifi_enabled {
-                            return ConnectivityStatus::new(true, "Connected".to_string());
-                        }
-                    }
+            if let Ok(proxy) = proxy
+                && let Ok(wifi_enabled) = proxy.get_property::<bool>("WirelessEnabled")
+            {
+                if let Ok(active_connections) =
+                    proxy.get_property::<Vec<zbus::zvariant::OwnedObjectPath>>("ActiveConnections")
+                    && !active_connections.is_empty()
+                    && wifi_enabled
+                {
+                    return ConnectivityStatus::new(true, "Connected".to_string());
+                }
 
-                    if wifi_enabled {
-                        return ConnectivityStatus::new(true, "Enabled".to_string());
-                    } else {
-                        return ConnectivityStatus::new(false, "Disabled".to_string());
-                    }
+                if wifi_enabled {
+                    return ConnectivityStatus::new(true, "Enabled".to_string());
+                } else {
+                    return ConnectivityStatus::new(false, "Disabled".to_string());
                 }
             }
             ConnectivityStatus::unknown()
@@ -54,28 +124,42 @@ pub fn read_wifi_status() -> ConnectivityStatus {
     }
 }
 
-/// Checks Bluetooth status via BlueZ DBus
+/// Checks Bluetooth status of the default adapter via BlueZ DBus
 pub fn read_bluetooth_status() -> ConnectivityStatus {
-    match Connection::system() {
-        Ok(conn) => {
-            let proxy = zbus::blocking::Proxy::new(
-                &conn,
-                "org.bluez",
-                "/org/bluez/hci0",
-                "org.bluez.Adapter1",
-            );
-
-            if let Ok(proxy) = proxy {
-                if let Ok(powered) = proxy.get_property::<bool>("Powered") {
-                    if powered {
-                        return ConnectivityStatus::new(true, "Enabled".to_string());
-                    } else {
-                        return ConnectivityStatus::new(false, "Disabled".to_string());
-                    }
-                }
-            }
-            ConnectivityStatus::unknown()
+    match default_adapter().map_err(ToggleError::from) {
+        Ok(Some(adapter)) if adapter.powered => {
+            ConnectivityStatus::new(true, "Enabled".to_string())
         }
-        Err(_) => ConnectivityStatus::new(false, "N/A".to_string()),
+        Ok(Some(_)) => ConnectivityStatus::new(false, "Disabled".to_string()),
+        Ok(None) => ConnectivityStatus::new(false, "No adapter".to_string()),
+        // bluetoothd isn't running, which is different from having no adapter
+        Err(ToggleError::DBus { name, .. })
+            if name.ends_with(".ServiceUnknown") || name.ends_with(".NameHasNoOwner") =>
+        {
+            ConnectivityStatus::new(false, "Bluetooth service not running".to_string())
+        }
+        Err(e) => ConnectivityStatus::new(false, format!("Unavailable: {}", e)),
     }
 }
+
+/// Enables or disables WiFi through NetworkManager
+pub fn set_wifi_enabled(enabled: bool) -> Result<(), ToggleError> {
+    let conn = Connection::system()?;
+    let proxy = zbus::blocking::Proxy::new(
+        &conn,
+        "org.freedesktop.NetworkManager",
+        "/org/freedesktop/NetworkManager",
+        "org.freedesktop.NetworkManager",
+    )?;
+    proxy.set_property("WirelessEnabled", enabled)?;
+    Ok(())
+}
+
+/// Powers the default Bluetooth adapter on or off through BlueZ
+pub fn set_bluetooth_powered(powered: bool) -> Result<(), ToggleError> {
+    let adapter = default_adapter()?.ok_or(ToggleError::NoAdapter)?;
+    let conn = Connection::system()?;
+    let proxy = zbus::blocking::Proxy::new(&conn, "org.bluez", adapter.path, "org.bluez.Adapter1")?;
+    proxy.set_property("Powered", powered)?;
+    Ok(())
+}
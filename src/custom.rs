@@ -0,0 +1,290 @@
+use crate::config::CustomModuleConfig;
+use crate::messages::AppMessage;
+use relm4::adw::glib;
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Longest wait before restarting a script that keeps exiting
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// Long-running scripts by module index, so a reload can stop them
+static RUNNING: Mutex<Vec<(usize, Child)>> = Mutex::new(Vec::new());
+
+/// What a custom module's script printed, in waybar's terms
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CustomOutput {
+    pub text: String,
+    pub tooltip: Option<String>,
+    #[serde(deserialize_with = "deserialize_classes")]
+    pub class: Vec<String>,
+    #[serde(deserialize_with = "deserialize_percentage")]
+    pub percentage: Option<u32>,
+}
+
+/// waybar scripts often print fractions like 45.5, which are rounded
+fn deserialize_percentage<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<f64>::deserialize(deserializer)?
+        .filter(|percentage| percentage.is_finite())
+        .map(|percentage| percentage.round().clamp(0.0, u32::MAX.into()) as u32))
+}
+
+/// waybar accepts a single class or a list of them
+fn deserialize_classes<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Classes {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Classes::deserialize(deserializer)? {
+        Classes::One(class) => vec![class],
+        Classes::Many(classes) => classes,
+    })
+}
+
+impl CustomOutput {
+    /// Fills "{text}" and "{percentage}" into the module's format
+    pub fn label(&self, format: &str) -> String {
+        format.replace("{text}", &self.text).replace(
+            "{percentage}",
+            &self.percentage.map(|p| p.to_string()).unwrap_or_default(),
+        )
+    }
+}
+
+/// Parses one JSON line, falling back to plain text
+pub fn parse_line(line: &str) -> CustomOutput {
+    let line = line.trim();
+    if line.starts_with('{')
+        && let Ok(output) = serde_json::from_str(line)
+    {
+        return output;
+    }
+    CustomOutput {
+        text: line.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Plain text output is text, tooltip and class on up to three lines like in waybar
+fn parse_output(output: &str) -> CustomOutput {
+    let mut lines = output.lines();
+    let first = lines.next().unwrap_or_default();
+    if first.trim_start().starts_with('{') {
+        return parse_line(first);
+    }
+    CustomOutput {
+        text: first.to_string(),
+        tooltip: lines.next().map(str::to_string),
+        class: lines
+            .next()
+            .map(|class| vec![class.to_string()])
+            .unwrap_or_default(),
+        percentage: None,
+    }
+}
+
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+/// Runs the module's script on its interval, or once reading every line it prints
+pub fn spawn_custom_module(
+    index: usize,
+    config: &CustomModuleConfig,
+    sender: relm4::Sender<AppMessage>,
+) {
+    let config = config.clone();
+    std::thread::spawn(move || match config.interval {
+        Some(interval) => loop {
+            match shell(&config.exec).output() {
+                Ok(output) => {
+                    let output = parse_output(&String::from_utf8_lossy(&output.stdout));
+                    sender.emit(AppMessage::CustomModuleUpdate(index, output));
+                }
+                Err(e) => eprintln!("Failed to run custom module '{}': {}", config.id, e),
+            }
+            std::thread::sleep(Duration::from_secs(interval.max(1)));
+        },
+        None => {
+            let mut delay = Duration::from_secs(1);
+            loop {
+                let started = Instant::now();
+                follow_script(index, &config, &sender);
+                // Only a script failing over and over again waits longer each time
+                if started.elapsed() > MAX_RESTART_DELAY {
+                    delay = Duration::from_secs(1);
+                }
+                eprintln!(
+                    "Custom module '{}' exited, restarting in {}s",
+                    config.id,
+                    delay.as_secs()
+                );
+                std::thread::sleep(delay);
+                delay = (delay * 2).min(MAX_RESTART_DELAY);
+            }
+        }
+    });
+}
+
+fn running() -> MutexGuard<'static, Vec<(usize, Child)>> {
+    RUNNING.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs a long-running script until it exits, sending every line it prints
+fn follow_script(index: usize, config: &CustomModuleConfig, sender: &relm4::Sender<AppMessage>) {
+    let mut child = match shell(&config.exec).stdout(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Failed to run custom module '{}': {}", config.id, e);
+            return;
+        }
+    };
+    let stdout = child.stdout.take();
+    running().push((index, child));
+
+    if let Some(stdout) = stdout {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            sender.emit(AppMessage::CustomModuleUpdate(index, parse_line(&line)));
+        }
+    }
+
+    let child = {
+        let mut running = running();
+        let position = running.iter().position(|(module, _)| *module == index);
+        position.map(|position| running.remove(position).1)
+    };
+    if let Some(mut child) = child {
+        let _ = child.wait();
+    }
+}
+
+/// Kills the long-running scripts, which would otherwise outlive a reload until their
+/// next write fails
+pub fn stop_scripts() {
+    for (_, mut child) in running().drain(..) {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Runs an on-click or on-scroll command in the background
+pub fn run_action(command: &str) {
+    let command = command.to_string();
+    std::thread::spawn(move || {
+        if let Err(e) = shell(&command).status() {
+            eprintln!("Failed to run '{}': {}", command, e);
+        }
+    });
+}
+
+/// A custom module's button in the bar
+pub struct CustomModule {
+    config: CustomModuleConfig,
+    pub button: gtk::Button,
+    label: gtk::Label,
+    /// The last output shown, its classes are replaced by the next one's
+    output: CustomOutput,
+}
+
+impl CustomModule {
+    pub fn new(config: CustomModuleConfig) -> Self {
+        let label = gtk::Label::new(None);
+        label.add_css_class("system-info-label");
+        let button = gtk::Button::builder().child(&label).visible(false).build();
+        button.add_css_class("flat");
+        button.add_css_class("custom-module");
+        button.add_css_class(&format!("custom-{}", config.id));
+
+        if let Some(command) = config.on_click.clone() {
+            button.connect_clicked(move |_| run_action(&command));
+        }
+        if let Some(command) = config.on_click_right.clone() {
+            let click = gtk::GestureClick::builder().button(3).build();
+            click.connect_pressed(move |_, _, _, _| run_action(&command));
+            button.add_controller(click);
+        }
+        if config.on_scroll_up.is_some() || config.on_scroll_down.is_some() {
+            let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+            let (up, down) = (config.on_scroll_up.clone(), config.on_scroll_down.clone());
+            scroll.connect_scroll(move |_, _, dy| {
+                // Scrolling up reports a negative delta
+                if let Some(command) = if dy < 0.0 { &up } else { &down } {
+                    run_action(command);
+                }
+                glib::Propagation::Stop
+            });
+            button.add_controller(scroll);
+        }
+
+        Self {
+            config,
+            button,
+            label,
+            output: CustomOutput::default(),
+        }
+    }
+
+    /// Shows the script's output, hiding the module while the text is empty like waybar does
+    pub fn update(&mut self, output: &CustomOutput) {
+        if *output == self.output {
+            return;
+        }
+        self.label.set_markup(&output.label(&self.config.format));
+        self.button.set_tooltip_markup(output.tooltip.as_deref());
+        for class in &self.output.class {
+            self.button.remove_css_class(class);
+        }
+        for class in &output.class {
+            self.button.add_css_class(class);
+        }
+        self.button.set_visible(!output.text.is_empty());
+        self.output = output.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_waybar_json() {
+        let output = parse_line(r#"{"text": "12°C", "class": "warm", "percentage": 45.5}"#);
+        assert_eq!(output.text, "12°C");
+        assert_eq!(output.class, ["warm"]);
+        assert_eq!(output.percentage, Some(46));
+        assert_eq!(
+            parse_line(r#"{"text": "a", "percentage": 7}"#).percentage,
+            Some(7)
+        );
+        assert_eq!(
+            parse_line(r#"{"text": "a", "percentage": null}"#).percentage,
+            None
+        );
+    }
+
+    #[test]
+    fn falls_back_to_plain_text() {
+        assert_eq!(parse_line("{not json").text, "{not json");
+        let output = parse_output("text\ntooltip\nclass\n");
+        assert_eq!(output.tooltip.as_deref(), Some("tooltip"));
+        assert_eq!(output.class, ["class"]);
+    }
+}
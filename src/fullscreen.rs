@@ -0,0 +1,42 @@
+use niri_ipc::socket::Socket;
+use niri_ipc::{Request, Response};
+
+fn request(request: Request) -> Option<Response> {
+    Socket::connect().ok()?.send(request).ok()?.ok()
+}
+
+/// Whether the active window on the given output (the focused one when None) covers the
+/// whole output. niri does not report fullscreen state over IPC, but a fullscreen window
+/// is the only one sized to the output.
+pub fn has_fullscreen_window(output: Option<&str>) -> bool {
+    let Some(Response::Workspaces(workspaces)) = request(Request::Workspaces) else {
+        return false;
+    };
+    let Some(workspace) = workspaces.iter().find(|workspace| match output {
+        Some(output) => workspace.is_active && workspace.output.as_deref() == Some(output),
+        None => workspace.is_focused,
+    }) else {
+        return false;
+    };
+    let (Some(window_id), Some(output_name)) = (workspace.active_window_id, &workspace.output)
+    else {
+        return false;
+    };
+
+    let Some(Response::Outputs(outputs)) = request(Request::Outputs) else {
+        return false;
+    };
+    let Some(logical) = outputs.get(output_name).and_then(|output| output.logical) else {
+        return false;
+    };
+    let Some(Response::Windows(windows)) = request(Request::Windows) else {
+        return false;
+    };
+    windows
+        .iter()
+        .find(|window| window.id == window_id)
+        .is_some_and(|window| {
+            let (width, height) = window.layout.window_size;
+            width >= logical.width as i32 && height >= logical.height as i32
+        })
+}
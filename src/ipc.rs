@@ -0,0 +1,163 @@
+use crate::messages::AppMessage;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// A command sent to the running bar, one JSON line per connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Request {
+    ToggleControlCenter,
+    ShowControlCenter,
+    HideControlCenter,
+    Reload,
+    Hide,
+    Show,
+    Toggle,
+    SetModuleText { id: String, text: String },
+    Status,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Reply {
+    Ok,
+    Status(Status),
+    Error(String),
+}
+
+/// What `adwbar status` prints
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub visible: bool,
+    pub control_center_visible: bool,
+    pub clock: String,
+    pub do_not_disturb: bool,
+    pub unread_notifications: usize,
+    pub timer: Option<String>,
+    pub media: Option<String>,
+    pub modules: Vec<ModuleStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleStatus {
+    pub id: String,
+    pub text: String,
+}
+
+/// Socket the bar is listening on, removed again before reloading
+static SERVED_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// $XDG_RUNTIME_DIR/adwbar-<display>.sock, or adwbar-<display>-<monitor>.sock for a bar
+/// started on one monitor, so bars of other sessions and monitors don't take each other's
+pub fn socket_path(monitor: Option<&str>) -> PathBuf {
+    let display = std::env::var_os("WAYLAND_DISPLAY").unwrap_or_else(|| "wayland-0".into());
+    // WAYLAND_DISPLAY may also be the absolute path of the compositor's socket
+    let display = Path::new(&display)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match monitor {
+        Some(monitor) => format!("adwbar-{}-{}.sock", display, monitor),
+        None => format!("adwbar-{}.sock", display),
+    };
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(name)
+}
+
+/// Listens on the socket and forwards each request to the bar, waiting for its reply
+pub fn serve(sender: relm4::Sender<AppMessage>, monitor: Option<&str>) -> std::io::Result<()> {
+    let path = socket_path(monitor);
+    if path.exists() {
+        // A socket nobody answers on is left over from a bar that crashed
+        if UnixStream::connect(&path).is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("another adwbar is listening on {}", path.display()),
+            ));
+        }
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    let _ = SERVED_PATH.set(path);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = handle_client(stream, &sender) {
+                        eprintln!("IPC client error: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("IPC connection failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle_client(stream: UnixStream, sender: &relm4::Sender<AppMessage>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+
+    let request = serde_json::from_str::<Request>(&line);
+    let reply = match &request {
+        Ok(Request::Reload) => Reply::Ok,
+        Ok(request) => {
+            let (reply, response) = relm4::channel();
+            sender.emit(AppMessage::IpcRequest(request.clone(), reply));
+            response
+                .recv_sync()
+                .unwrap_or_else(|| Reply::Error("The bar did not answer".to_string()))
+        }
+        Err(e) => Reply::Error(format!("Invalid request: {}", e)),
+    };
+    let mut stream = &stream;
+    writeln!(stream, "{}", serde_json::to_string(&reply)?)?;
+
+    if let Ok(Request::Reload) = request {
+        reload();
+    }
+    Ok(())
+}
+
+/// Restarts the bar in place with the same arguments so the config is read again
+pub fn reload() {
+    let Ok(exe) = std::env::current_exe() else {
+        eprintln!("Failed to reload: could not find the adwbar executable");
+        return;
+    };
+    crate::custom::stop_scripts();
+    // The socket is bound again by the new process
+    if let Some(path) = SERVED_PATH.get() {
+        let _ = std::fs::remove_file(path);
+    }
+    let e = std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .exec();
+    eprintln!("Failed to reload: {}", e);
+}
+
+/// Sends a request to the running bar and waits for its reply
+pub fn send(request: &Request, monitor: Option<&str>) -> std::io::Result<Reply> {
+    let path = socket_path(monitor);
+    let mut stream = UnixStream::connect(&path).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!(
+                "Could not connect to {}, is adwbar running? ({})",
+                path.display(),
+                e
+            ),
+        )
+    })?;
+    writeln!(stream, "{}", serde_json::to_string(request)?)?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
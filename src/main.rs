@@ -1,40 +1,190 @@
+mod audio;
+mod bar;
+mod bar_service;
+mod bluetooth;
+mod brightness;
+mod calendar;
+mod clock;
+mod config;
 mod config_window;
 mod connectivity;
+mod custom;
+mod fullscreen;
+mod ipc;
 mod messages;
+mod mpris;
+mod networks;
+mod notification_popups;
+mod notifications;
+mod overview;
+mod power_profiles;
+mod rfkill;
+mod session;
 mod system_monitor;
+mod theme;
+mod timer;
 mod window_list;
 
 use chrono::Local;
 use clap::Parser;
 use gtk::prelude::*;
-use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use gtk4_layer_shell::LayerShell;
 use relm4::adw::glib;
-use relm4::gtk::{Align, Image};
 use relm4::prelude::*;
+use std::collections::HashSet;
+use std::path::PathBuf;
 
+use audio::{
+    AudioDevice, AudioWatcher, DeviceKind, PactlBackend, change_volume, stepped_volume,
+    toggle_mute, watch_audio,
+};
+use bar::{Bar, BarInit, BarState, next_event_label};
+use bar_service::BarService;
+use brightness::{
+    Backlight, BacklightKind, read_backlight, set_raw_brightness, stepped_brightness,
+};
+use calendar::{Calendar, Event, today};
+use chrono::{Duration, NaiveDate};
+use clock::{ZoneClock, format_local, full_date, until_next_tick, week_label};
+use config::{
+    BarConfig, CalendarConfig, ClockConfig, Config, CustomModuleConfig, FullscreenBehavior,
+    NotificationsConfig, TimerConfig,
+};
+use config_window::ConfigWindowInit;
 use connectivity::{ConnectivityStatus, read_bluetooth_status, read_wifi_status};
-use messages::AppMessage;
+use custom::{CustomOutput, parse_line, spawn_custom_module};
+use fullscreen::has_fullscreen_window;
+use ipc::{ModuleStatus, Reply, Request, Status};
+use messages::{AppMessage, BarMessage, ConfMessage, ConfOutput, PopupMessage, PopupOutput};
+use mpris::{Player, PlayersWatcher, active_player, watch_players};
+use networks::{is_vpn_active, watch_connections};
+use notification_popups::NotificationPopups;
+use notifications::{
+    CloseReason, DndReason, NotificationDaemon, URGENCY_CRITICAL, in_schedule, send_notification,
+};
+use power_profiles::{PowerProfiles, read_power_profiles};
+use std::process::Command;
 use system_monitor::{read_cpu_usage, read_gpu_usage};
-use window_list::update_window_list;
+use timer::{Timer, TimerKind, load_timer, save_timer};
+use window_list::{Workspaces, read_workspaces};
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "adwbar")]
 #[command(about = "A status bar application", long_about = None)]
 struct Args {
-    /// Monitor name to display the bar on (e.g., HDMI-A-1, DP-1)
+    /// Monitor name to display the bar on (e.g., HDMI-A-1, DP-1), may be repeated
     #[arg(short, long)]
-    monitor: Option<String>,
+    monitor: Vec<String>,
+
+    /// Show a bar on every monitor, following monitors being plugged in and out
+    #[arg(short, long)]
+    all_monitors: bool,
+
+    /// Config file to use instead of ~/.config/adwbar/config.toml
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+impl Args {
+    /// The monitor of a bar started for exactly one `--monitor`
+    fn single_monitor(&self) -> Option<&str> {
+        match self.monitor.as_slice() {
+            [monitor] if !self.all_monitors => Some(monitor),
+            _ => None,
+        }
+    }
+}
+
+/// Commands sent to the running bar instead of starting a new one
+#[derive(clap::Subcommand, Debug, Clone)]
+enum Commands {
+    /// Show or hide the control center
+    ToggleControlCenter,
+    /// Restart the bar, reading the config again
+    Reload,
+    /// Hide the bar
+    Hide,
+    /// Show the bar
+    Show,
+    /// Show or hide the bar
+    Toggle,
+    /// Set the text of a custom module, plain or waybar JSON
+    SetModuleText { id: String, text: String },
+    /// Print what the bar is showing
+    Status {
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 struct App {
+    /// One bar per monitor, or a single one on the compositor's choice of output
+    bars: Vec<(Option<gtk::gdk::Monitor>, Controller<Bar>)>,
+    /// The state last sent to the bars
+    sent_state: Option<BarState>,
+    /// Connectors that get a bar, every monitor when empty and `all_monitors` is set
+    monitors: Vec<String>,
+    all_monitors: bool,
+    bar_config: BarConfig,
+    visible: bool,
+    /// Auto-hiding bars stay revealed until hidden again through IPC
+    reveal_pinned: bool,
+    overview_open: bool,
     clock: String,
-    window_list_container: gtk::Box,
+    clock_tooltip: String,
+    clock_config: ClockConfig,
+    /// Showing `alt_format` instead of `format`
+    clock_alt: bool,
+    /// The pending clock tick, so a format switch can move it
+    clock_tick: Option<glib::SourceId>,
+    /// Extra clocks for other time zones next to the main one
+    bar_clocks: Vec<(ZoneClock, String)>,
+    date: String,
+    week: String,
+    zone_clocks: Vec<(ZoneClock, String)>,
+    calendar_config: CalendarConfig,
+    calendar: Calendar,
+    /// Day picked in the calendar popover
+    selected_day: NaiveDate,
+    /// Day the event list was last built for, so it follows midnight
+    events_day: NaiveDate,
+    events: Vec<(String, Vec<Event>)>,
+    next_event: Option<Event>,
+    timer: Option<Timer>,
+    timer_config: TimerConfig,
+    custom_modules: Vec<CustomModuleConfig>,
+    custom_outputs: Vec<CustomOutput>,
+    workspaces: Workspaces,
     system_info: (String, String),
     wifi_status: ConnectivityStatus,
     bluetooth_status: ConnectivityStatus,
+    vpn_active: bool,
+    audio: AudioWatcher,
+    volume: Option<AudioDevice>,
+    brightness: Option<Backlight>,
+    power_profiles: Option<PowerProfiles>,
+    media: Option<Player>,
+    players: PlayersWatcher,
     config_window: Controller<config_window::ConfigWindow>,
+    notification_popups: Controller<NotificationPopups>,
+    notification_daemon: Option<NotificationDaemon>,
+    bar_service: Option<BarService>,
+    unread_notifications: HashSet<u32>,
+    notifications_config: NotificationsConfig,
+    /// Connector name of the output the bar is on, if only one was chosen
+    monitor: Option<String>,
+    dnd: Option<DndReason>,
+    /// What the schedule or a fullscreen window asks for
+    dnd_auto: Option<DndReason>,
+    /// Set by toggling by hand, until the automatic state changes
+    dnd_override: Option<bool>,
 }
 
+/// The app itself has no visible window, it owns the data sources and the bars show them
 #[relm4::component]
 impl SimpleComponent for App {
     type Input = AppMessage;
@@ -42,86 +192,8 @@ impl SimpleComponent for App {
     type Init = Args;
 
     view! {
-        window = adw::ApplicationWindow {
-            set_title: Some("Layer Shell with Adwaita"),
-            set_default_size: (24, 24),
-            add_css_class: "main-bar-window",
-
-            adw::ToolbarView {
-                add_top_bar = &adw::HeaderBar {
-                    add_css_class: "main-container",
-                    set_show_end_title_buttons: false,
-                    set_show_start_title_buttons: false,
-
-                    // window list container
-                    #[local_ref]
-                    pack_start = &window_list_container -> gtk::Box {
-                        set_orientation: gtk::Orientation::Horizontal,
-                        set_spacing: 5,
-                        set_halign: Align::Start,
-                        set_hexpand: true,
-                    },
-
-                    // clock container
-                    #[wrap(Some)]
-                    set_title_widget = &gtk::Button {
-                        add_css_class: "clock-container",
-                        add_css_class: "flat",
-                        set_halign: Align::Center,
-                        gtk::Label {
-                            #[watch]
-                            set_label: &model.clock,
-                            add_css_class: "title-2",
-                        }
-                    },
-
-                    // System info container
-                    pack_end = &gtk::Box {
-                        add_css_class: "system-info-container",
-                        set_halign: Align::End,
-                        set_hexpand: true,
-                        set_spacing: 5,
-                        gtk::Button {
-                            add_css_class: "system-info-button",
-                            add_css_class: "flat",
-                            set_halign: Align::End,
-                            connect_clicked[sender] => move |_| {
-                                sender.input(AppMessage::SystemInfoClicked);
-                            },
-                            gtk::Box {
-                                set_orientation: gtk::Orientation::Horizontal,
-                                set_spacing: 8,
-                                // System info
-                                gtk::Label {
-                                    #[watch]
-                                    set_label: &format!("CPU: {} GPU: {}", model.system_info.0, model.system_info.1),
-                                    add_css_class: "system-info-label",
-                                },
-                                // WiFi icon
-                                Image {
-                                    #[watch]
-                                    set_icon_name: Some(if model.wifi_status.enabled {
-                                        "network-wireless-signal-excellent-symbolic"
-                                    } else {
-                                        "network-wireless-disabled-symbolic"
-                                    }),
-                                    set_pixel_size: 16,
-                                },
-                                // Bluetooth icon
-                                Image {
-                                    #[watch]
-                                    set_icon_name: Some(if model.bluetooth_status.enabled {
-                                        "bluetooth-active-symbolic"
-                                    } else {
-                                        "bluetooth-disabled-symbolic"
-                                    }),
-                                    set_pixel_size: 16,
-                                },
-                            }
-                        }
-                    }
-                }
-            }
+        gtk::Window {
+            set_title: Some("adwbar"),
         }
     }
 
@@ -130,97 +202,244 @@ impl SimpleComponent for App {
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        // Initialize layer shell
-        root.init_layer_shell();
-        root.set_layer(Layer::Top);
-        root.auto_exclusive_zone_enable();
-        root.set_anchor(Edge::Top, true);
-        root.set_anchor(Edge::Left, true);
-        root.set_anchor(Edge::Right, true);
-        root.set_height_request(24);
-
-        // Set monitor if specified
-        if let Some(monitor_name) = args.monitor {
-            let display = gtk::gdk::Display::default().expect("Could not get default display");
-            let monitor_list = display.monitors();
-            let num_monitors = monitor_list.n_items();
-
-            let mut found = false;
-            for i in 0..num_monitors {
-                if let Some(monitor_obj) = monitor_list.item(i) {
-                    let monitor = monitor_obj
-                        .downcast::<gtk::gdk::Monitor>()
-                        .expect("Failed to downcast to Monitor");
-                    if let Some(connector) = monitor.connector() {
-                        if connector.as_str() == monitor_name {
-                            root.set_monitor(Some(&monitor));
-                            found = true;
-                            break;
-                        }
-                    }
-                }
-            }
+        let config = Config::load(args.config.as_deref());
+        theme::apply(&config.theme);
 
-            if !found {
-                eprintln!(
-                    "Warning: Monitor '{}' not found, using default",
-                    monitor_name
-                );
-                eprintln!("Available monitors:");
-                for i in 0..num_monitors {
-                    if let Some(monitor_obj) = monitor_list.item(i) {
-                        let monitor = monitor_obj
-                            .downcast::<gtk::gdk::Monitor>()
-                            .expect("Failed to downcast to Monitor");
-                        if let Some(connector) = monitor.connector() {
-                            eprintln!("  - {}", connector);
-                        }
-                    }
-                }
-            }
-        }
+        // Initialize model
+        let clock_config = config.clock.clone();
+        let bar_clocks = ZoneClock::load(&clock_config.bar_time_zones)
+            .into_iter()
+            .map(|zone| (zone, String::new()))
+            .collect();
+        let zone_clocks = ZoneClock::load(&clock_config.time_zones)
+            .into_iter()
+            .map(|zone| (zone, String::new()))
+            .collect();
 
-        // Load CSS
-        let css_provider = gtk::CssProvider::new();
-        css_provider.load_from_bytes(&glib::Bytes::from(include_bytes!("style.css")));
-        gtk::style_context_add_provider_for_display(
-            &gtk::gdk::Display::default().unwrap(),
-            &css_provider,
-            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
+        let bar_config = config.bar.clone();
+        let notifications_config = config.notifications.clone();
+        let calendar_config = config.calendar.clone();
 
-        // Initialize model
-        let clock = Local::now().format("%H:%M").to_string();
-        let window_list_container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        let custom_modules = config.custom_modules.clone();
+        for (index, module_config) in custom_modules.iter().enumerate() {
+            spawn_custom_module(index, module_config, sender.input_sender().clone());
+        }
+        let timer_config = config.timer.clone();
+        let has_schedule = notifications_config.dnd_from.is_some();
+        if has_schedule && notifications_config.dnd_schedule().is_none() {
+            eprintln!("Warning: Ignoring Do Not Disturb schedule, times must look like \"22:00\"");
+        }
+        let notification_daemon = if config.notifications.enabled {
+            NotificationDaemon::start(sender.input_sender().clone())
+                .inspect_err(|e| eprintln!("Failed to start notification daemon: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        let notification_popups = NotificationPopups::builder()
+            .launch(notifications_config.clone())
+            .forward(sender.input_sender(), |output| match output {
+                PopupOutput::Dismissed(id) => {
+                    AppMessage::CloseNotification(id, CloseReason::Dismissed)
+                }
+                PopupOutput::ActionInvoked(id, key) => {
+                    AppMessage::InvokeNotificationAction(id, key)
+                }
+            });
 
+        let audio = watch_audio(Box::new(PactlBackend), {
+            let sender = sender.input_sender().clone();
+            move |devices| sender.emit(AppMessage::VolumeUpdate(devices))
+        });
+        watch_connections({
+            let sender = sender.input_sender().clone();
+            // Runs on the watching thread, off the main loop
+            move || sender.emit(AppMessage::VpnUpdate(is_vpn_active()))
+        });
+        let players = watch_players({
+            let sender = sender.input_sender().clone();
+            move |players| sender.emit(AppMessage::MediaUpdate(players))
+        });
         let config_window = config_window::ConfigWindow::builder()
             .transient_for(&root)
-            .launch(())
-            .detach();
+            .launch(ConfigWindowInit {
+                config,
+                audio: audio.clone(),
+                players: players.clone(),
+            })
+            .forward(sender.input_sender(), |output| match output {
+                ConfOutput::DismissNotification(id) => {
+                    AppMessage::CloseNotification(id, CloseReason::Dismissed)
+                }
+                ConfOutput::InvokeNotificationAction(id, key) => {
+                    AppMessage::InvokeNotificationAction(id, key)
+                }
+                ConfOutput::SetDoNotDisturb(enabled) => AppMessage::SetDoNotDisturb(enabled),
+            });
+
+        if let Err(e) = ipc::serve(sender.input_sender().clone(), args.single_monitor()) {
+            eprintln!("Failed to start IPC socket: {}", e);
+        }
+        let bar_service = BarService::start(sender.input_sender().clone())
+            .inspect_err(|e| eprintln!("Failed to export the bar on D-Bus: {}", e))
+            .ok();
 
-        let model = App {
-            clock,
-            window_list_container: window_list_container.clone(),
+        let mut model = App {
+            bars: Vec::new(),
+            sent_state: None,
+            monitor: args.single_monitor().map(str::to_string),
+            monitors: args.monitor,
+            all_monitors: args.all_monitors,
+            bar_config,
+            visible: true,
+            reveal_pinned: false,
+            overview_open: false,
+            clock: String::new(),
+            clock_tooltip: String::new(),
+            clock_config,
+            clock_alt: false,
+            clock_tick: None,
+            bar_clocks,
+            date: full_date(),
+            week: week_label(),
+            zone_clocks,
+            calendar: Calendar::load(&calendar_config.paths),
+            calendar_config,
+            selected_day: today(),
+            events_day: today(),
+            events: Vec::new(),
+            next_event: None,
+            timer: load_timer(),
+            timer_config,
+            custom_outputs: vec![CustomOutput::default(); custom_modules.len()],
+            custom_modules,
+            workspaces: Workspaces::new(),
             system_info: (String::new(), String::new()),
             wifi_status: ConnectivityStatus::unknown(),
             bluetooth_status: ConnectivityStatus::unknown(),
+            vpn_active: false,
+            audio,
+            volume: None,
+            brightness: read_backlight(BacklightKind::Screen),
+            power_profiles: read_power_profiles(),
+            media: None,
+            players,
             config_window,
+            notification_popups,
+            notification_daemon,
+            bar_service,
+            unread_notifications: HashSet::new(),
+            notifications_config,
+            dnd: None,
+            dnd_auto: None,
+            dnd_override: None,
         };
 
         let widgets = view_output!();
 
+        model.update_clock();
+        model.schedule_clock_tick(&sender);
+        model.rebuild_events();
+        model.update_next_event();
+        model.sync_bars(&sender);
+        warn_missing_monitors(&model.monitors);
+        if let [(Some(monitor), _)] = model.bars.as_slice() {
+            model
+                .notification_popups
+                .widget()
+                .set_monitor(Some(monitor));
+        }
         setup_timers(&sender);
+        if model.bar_config.autohide {
+            overview::watch_overview(sender.input_sender().clone());
+        }
+        // Move or hide the bars while a window is fullscreen under them
+        if model.bar_config.fullscreen != FullscreenBehavior::Stay {
+            let sender = sender.clone();
+            glib::timeout_add_seconds_local(1, move || {
+                sender.input(AppMessage::FullscreenUpdate);
+                glib::ControlFlow::Continue
+            });
+        }
+
+        // Follow monitors being plugged in and out
+        if model.all_monitors || !model.monitors.is_empty() {
+            let display = gtk::gdk::Display::default().expect("Could not get default display");
+            let sender = sender.clone();
+            display.monitors().connect_items_changed(move |_, _, _, _| {
+                sender.input(AppMessage::MonitorsChanged);
+            });
+        }
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
+            AppMessage::MonitorsChanged => self.sync_bars(&sender),
             AppMessage::ClockUpdate => {
-                self.clock = Local::now().format("%H:%M").to_string();
+                self.update_clock();
+                self.schedule_clock_tick(&sender);
+            }
+            AppMessage::ClockToggleFormat => {
+                if self.clock_config.alt_format.is_some() {
+                    self.clock_alt = !self.clock_alt;
+                    self.update_clock();
+                    // The other format may show seconds where this one didn't
+                    self.schedule_clock_tick(&sender);
+                }
+            }
+            AppMessage::TimerTick => {
+                let finished = self.timer.as_ref().is_some_and(Timer::is_finished);
+                if finished {
+                    self.finish_timer();
+                }
+            }
+            AppMessage::StartCountdown(minutes) => {
+                self.timer = Some(Timer::countdown(minutes));
+                save_timer(self.timer.as_ref());
+            }
+            AppMessage::StartPomodoro => {
+                self.timer = Some(Timer::pomodoro(&self.timer_config));
+                save_timer(self.timer.as_ref());
+            }
+            AppMessage::StartStopwatch => {
+                self.timer = Some(Timer::stopwatch());
+                save_timer(self.timer.as_ref());
+            }
+            AppMessage::TimerPauseResume => {
+                if let Some(timer) = &mut self.timer {
+                    if timer.is_running() {
+                        timer.pause();
+                    } else {
+                        timer.resume();
+                    }
+                }
+                save_timer(self.timer.as_ref());
+            }
+            AppMessage::TimerStop => {
+                self.timer = None;
+                save_timer(None);
+            }
+            AppMessage::CalendarReload => {
+                self.calendar = Calendar::load(&self.calendar_config.paths);
+                self.rebuild_events();
+                self.update_next_event();
+            }
+            AppMessage::CalendarUpdate => {
+                if self.events_day != today() {
+                    self.rebuild_events();
+                }
+                self.update_next_event();
+            }
+            AppMessage::CalendarDaySelected(date) => {
+                self.selected_day = date;
+                self.rebuild_events();
             }
             AppMessage::WindowListUpdate => {
-                update_window_list(&self.window_list_container);
+                if let Some(workspaces) = read_workspaces() {
+                    self.workspaces = workspaces;
+                }
             }
             AppMessage::SystemInfoUpdate => {
                 self.system_info = (read_cpu_usage(), read_gpu_usage());
@@ -229,18 +448,544 @@ impl SimpleComponent for App {
                 self.wifi_status = read_wifi_status();
                 self.bluetooth_status = read_bluetooth_status();
             }
+            AppMessage::VpnUpdate(active) => {
+                self.vpn_active = active;
+                self.config_window.emit(ConfMessage::NetworksChanged);
+            }
+            AppMessage::VolumeUpdate(devices) => {
+                self.volume = devices.default_device(DeviceKind::Output).cloned();
+                self.config_window.emit(ConfMessage::AudioChanged(devices));
+            }
+            AppMessage::VolumeScroll(dy) => {
+                if let Some(device) = &mut self.volume {
+                    // Scrolling up reports a negative delta
+                    let step = if dy < 0.0 { 5 } else { -5 };
+                    let volume = stepped_volume(device, step);
+                    let current = device.clone();
+                    self.audio.change(
+                        move |backend| change_volume(backend, DeviceKind::Output, &current, volume),
+                        |e| eprintln!("Failed to set volume: {}", e),
+                    );
+                    // Shown until the audio thread reads the devices again, which undoes it
+                    // if the change failed
+                    device.volume = volume;
+                }
+            }
+            AppMessage::VolumeMuteToggle => {
+                if let Some(device) = &mut self.volume {
+                    let current = device.clone();
+                    self.audio.change(
+                        move |backend| toggle_mute(backend, DeviceKind::Output, &current),
+                        |e| eprintln!("Failed to mute: {}", e),
+                    );
+                    device.muted = !device.muted;
+                }
+            }
+            AppMessage::BrightnessUpdate => {
+                self.brightness = read_backlight(BacklightKind::Screen);
+            }
+            AppMessage::BrightnessScroll(dy) => {
+                if let Some(backlight) = &self.brightness {
+                    let step = if dy < 0.0 { 5 } else { -5 };
+                    let value = stepped_brightness(backlight, step);
+                    if value != backlight.brightness
+                        && let Err(e) = set_raw_brightness(backlight, value)
+                    {
+                        eprintln!("Failed to set brightness: {}", e);
+                    }
+                }
+                self.brightness = read_backlight(BacklightKind::Screen);
+            }
+            AppMessage::PowerProfileUpdate => {
+                self.power_profiles = read_power_profiles();
+            }
+            AppMessage::MediaUpdate(players) => {
+                self.media = active_player(&players).cloned();
+                self.config_window
+                    .emit(ConfMessage::PlayersChanged(players));
+            }
+            AppMessage::MediaPlayPause => {
+                self.control_media("Failed to play or pause", mpris::play_pause);
+            }
+            AppMessage::MediaScroll(dy) => {
+                // Scrolling up skips ahead
+                if dy < 0.0 {
+                    self.control_media("Failed to skip", mpris::next);
+                } else {
+                    self.control_media("Failed to go back", mpris::previous);
+                }
+            }
+            AppMessage::CustomModuleUpdate(index, output) => {
+                if let Some(module_output) = self.custom_outputs.get_mut(index) {
+                    *module_output = output;
+                }
+            }
             AppMessage::SystemInfoClicked => {
                 self.config_window.widget().set_visible(true);
+                self.unread_notifications.clear();
+            }
+            AppMessage::NotificationReceived(notification) => {
+                self.unread_notifications.insert(notification.id);
+                // Do Not Disturb only hides popups, critical ones still show
+                if self.dnd.is_none() || notification.urgency == URGENCY_CRITICAL {
+                    self.notification_popups
+                        .emit(PopupMessage::Show(notification.clone()));
+                }
+                self.config_window
+                    .emit(ConfMessage::NotificationReceived(notification));
+            }
+            AppMessage::CloseNotification(id, reason) => {
+                self.unread_notifications.remove(&id);
+                self.notification_popups.emit(PopupMessage::Close(id));
+                self.config_window.emit(ConfMessage::RemoveNotification(id));
+                if let Some(daemon) = &self.notification_daemon {
+                    daemon.notify_closed(id, reason);
+                }
+            }
+            AppMessage::InvokeNotificationAction(id, key) => {
+                if let Some(daemon) = &self.notification_daemon {
+                    daemon.invoke_action(id, &key);
+                }
+                sender.input(AppMessage::CloseNotification(id, CloseReason::Dismissed));
+            }
+            AppMessage::DoNotDisturbUpdate => self.update_dnd(),
+            AppMessage::ToggleDoNotDisturb => {
+                self.dnd_override = Some(self.dnd.is_none());
+                self.update_dnd();
+            }
+            AppMessage::SetDoNotDisturb(enabled) => {
+                self.dnd_override = Some(enabled);
+                self.update_dnd();
+            }
+            AppMessage::OverviewChanged(open) => self.overview_open = open,
+            AppMessage::FullscreenUpdate => {
+                for (monitor, bar) in &self.bars {
+                    let connector = monitor.as_ref().and_then(|monitor| monitor.connector());
+                    bar.emit(BarMessage::Fullscreen(has_fullscreen_window(
+                        connector.as_deref(),
+                    )));
+                }
+            }
+            AppMessage::IpcRequest(request, reply) => {
+                reply.emit(self.handle_request(request, &sender));
             }
         }
+        self.update_bars();
+        self.publish_status();
+    }
+}
+
+impl App {
+    /// Adds and removes bars to match the connected monitors
+    fn sync_bars(&mut self, sender: &ComponentSender<Self>) {
+        if !self.all_monitors && self.monitors.is_empty() {
+            if self.bars.is_empty() {
+                let bar = self.launch_bar(None, sender);
+                self.bars.push((None, bar));
+            }
+            return;
+        }
+
+        let wanted: Vec<gtk::gdk::Monitor> = connected_monitors()
+            .into_iter()
+            .filter(|monitor| {
+                self.all_monitors
+                    || monitor.connector().is_some_and(|connector| {
+                        self.monitors.iter().any(|name| *name == connector)
+                    })
+            })
+            .collect();
+        self.bars.retain(|(monitor, bar)| {
+            let keep = monitor
+                .as_ref()
+                .is_some_and(|monitor| wanted.contains(monitor));
+            if !keep {
+                bar.widget().destroy();
+            }
+            keep
+        });
+        for monitor in wanted {
+            let has_bar = self
+                .bars
+                .iter()
+                .any(|(bar_monitor, _)| bar_monitor.as_ref() == Some(&monitor));
+            if !has_bar {
+                let bar = self.launch_bar(Some(&monitor), sender);
+                self.bars.push((Some(monitor), bar));
+            }
+        }
+    }
+
+    fn launch_bar(
+        &self,
+        monitor: Option<&gtk::gdk::Monitor>,
+        sender: &ComponentSender<Self>,
+    ) -> Controller<Bar> {
+        Bar::builder()
+            .launch(BarInit {
+                monitor: monitor.cloned(),
+                state: self.bar_state(),
+                custom_modules: self.custom_modules.clone(),
+                config: self.bar_config.clone(),
+                timer_config: self.timer_config.clone(),
+            })
+            .forward(sender.input_sender(), |message| message)
+    }
+
+    /// Sends every bar the current state, unless nothing they show changed
+    fn update_bars(&mut self) {
+        let state = self.bar_state();
+        if self.sent_state.as_ref() == Some(&state) {
+            return;
+        }
+        for (_, bar) in &self.bars {
+            bar.emit(BarMessage::Update(Box::new(state.clone())));
+        }
+        self.sent_state = Some(state);
+    }
+
+    fn bar_state(&self) -> BarState {
+        BarState {
+            visible: self.visible,
+            clock: self.clock.clone(),
+            clock_tooltip: self.clock_tooltip.clone(),
+            bar_clocks: self
+                .bar_clocks
+                .iter()
+                .map(|(_, text)| text.clone())
+                .collect(),
+            date: self.date.clone(),
+            week: self.week.clone(),
+            zone_clocks: self
+                .zone_clocks
+                .iter()
+                .map(|(zone, time)| (zone.city.clone(), time.clone()))
+                .collect(),
+            events: self.events.clone(),
+            next_event: self.next_event.clone(),
+            next_event_text: self
+                .next_event
+                .as_ref()
+                .map(next_event_label)
+                .unwrap_or_default(),
+            timer: self.timer.clone(),
+            timer_text: self.timer.as_ref().map(Timer::display).unwrap_or_default(),
+            workspaces: self.workspaces.clone(),
+            custom_outputs: self.custom_outputs.clone(),
+            system_info: self.system_info.clone(),
+            wifi_status: self.wifi_status.clone(),
+            bluetooth_status: self.bluetooth_status.clone(),
+            vpn_active: self.vpn_active,
+            volume: self.volume.clone(),
+            brightness: self.brightness.clone(),
+            power_profiles: self.power_profiles.clone(),
+            media: self.media.clone(),
+            notifications_enabled: self.notification_daemon.is_some(),
+            unread_notifications: self.unread_notifications.len(),
+            dnd: self.dnd.is_some(),
+            overview_open: self.overview_open,
+            reveal_pinned: self.reveal_pinned,
+        }
+    }
+
+    fn handle_request(&mut self, request: Request, sender: &ComponentSender<Self>) -> Reply {
+        match request {
+            Request::ShowControlCenter => sender.input(AppMessage::SystemInfoClicked),
+            Request::HideControlCenter => self.config_window.widget().set_visible(false),
+            Request::ToggleControlCenter => {
+                let control_center = self.config_window.widget();
+                if control_center.is_visible() {
+                    control_center.set_visible(false);
+                } else {
+                    sender.input(AppMessage::SystemInfoClicked);
+                }
+            }
+            Request::Hide | Request::Show | Request::Toggle => {
+                // Auto-hiding bars slide in and out instead of being unmapped
+                let shown = if self.bar_config.autohide {
+                    &mut self.reveal_pinned
+                } else {
+                    &mut self.visible
+                };
+                *shown = match request {
+                    Request::Hide => false,
+                    Request::Show => true,
+                    _ => !*shown,
+                };
+            }
+            Request::SetModuleText { id, text } => {
+                let Some(index) = self
+                    .custom_modules
+                    .iter()
+                    .position(|module| module.id == id)
+                else {
+                    return Reply::Error(format!("No custom module with id '{}'", id));
+                };
+                self.custom_outputs[index] = parse_line(&text);
+            }
+            Request::Status => return Reply::Status(self.status()),
+            // Handled by the socket thread and D-Bus service, which restart the whole process
+            Request::Reload => {}
+        }
+        Reply::Ok
+    }
+
+    /// Tells D-Bus listeners about properties that changed, whatever changed them
+    fn publish_status(&mut self) {
+        if self.bar_service.is_none() {
+            return;
+        }
+        let status = self.status();
+        if let Some(service) = &mut self.bar_service {
+            service.update(status);
+        }
+    }
+
+    fn status(&self) -> Status {
+        Status {
+            visible: if self.bar_config.autohide {
+                self.reveal_pinned
+            } else {
+                self.visible
+            },
+            control_center_visible: self.config_window.widget().is_visible(),
+            clock: self.clock.clone(),
+            do_not_disturb: self.dnd.is_some(),
+            unread_notifications: self.unread_notifications.len(),
+            timer: self.timer.as_ref().map(Timer::display),
+            media: self.media.as_ref().map(Player::now_playing),
+            modules: self
+                .custom_modules
+                .iter()
+                .zip(&self.custom_outputs)
+                .map(|(module, output)| ModuleStatus {
+                    id: module.id.clone(),
+                    text: output.text.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Announces a finished countdown or pomodoro phase, pomodoros carry on with the next phase
+    fn finish_timer(&mut self) {
+        let Some(timer) = &mut self.timer else {
+            return;
+        };
+        let (summary, body) = match timer.kind {
+            TimerKind::Pomodoro => {
+                let finished = timer.phase.label();
+                timer.next_phase(&self.timer_config);
+                (
+                    format!("{} is over", finished),
+                    format!("{} for {}", timer.phase.label(), timer.display()),
+                )
+            }
+            _ => {
+                self.timer = None;
+                ("Timer finished".to_string(), String::new())
+            }
+        };
+        save_timer(self.timer.as_ref());
+
+        std::thread::spawn(move || {
+            if let Err(e) = send_notification(&summary, &body) {
+                eprintln!("Failed to send timer notification: {}", e);
+            }
+        });
+        if let Some(sound) = self.timer_config.sound.clone() {
+            // Waiting off the main thread reaps paplay once the sound ends
+            std::thread::spawn(move || {
+                if let Err(e) = Command::new("paplay").arg(sound).status() {
+                    eprintln!("Failed to play timer sound: {}", e);
+                }
+            });
+        }
+    }
+
+    fn clock_format(&self) -> &str {
+        match &self.clock_config.alt_format {
+            Some(alt_format) if self.clock_alt => alt_format,
+            _ => &self.clock_config.format,
+        }
+    }
+
+    fn update_clock(&mut self) {
+        self.clock = format_local(self.clock_format());
+        self.clock_tooltip = format_local(&self.clock_config.tooltip_format);
+        let format = self.clock_format().to_string();
+        for (zone, text) in &mut self.bar_clocks {
+            *text = format!("{} {}", zone.city, zone.format(&format));
+        }
+        self.date = full_date();
+        self.week = week_label();
+        for (zone, time) in &mut self.zone_clocks {
+            *time = zone.time();
+        }
+    }
+
+    /// Sends a command to the shown player on a thread, players may be slow to answer
+    fn control_media(&self, failure: &'static str, control: fn(&Player) -> zbus::Result<()>) {
+        let Some(player) = self.media.clone() else {
+            return;
+        };
+        let players = self.players.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = control(&player) {
+                eprintln!("{}: {}", failure, e);
+            }
+            players.refresh();
+        });
+    }
+
+    /// The next tick comes every second only when something on screen shows seconds
+    fn until_next_tick(&self) -> std::time::Duration {
+        until_next_tick(&[self.clock_format(), &self.clock_config.tooltip_format])
+    }
+
+    /// Replaces the pending tick, so there is never more than one
+    fn schedule_clock_tick(&mut self, sender: &ComponentSender<Self>) {
+        if let Some(tick) = self.clock_tick.take() {
+            remove_pending(&tick);
+        }
+        self.clock_tick = Some(schedule_clock_tick(sender, self.until_next_tick()));
+    }
+
+    /// Lists today's events, followed by the selected day's when another day is picked
+    fn rebuild_events(&mut self) {
+        if self.calendar_config.paths.is_empty() {
+            return;
+        }
+
+        let today = today();
+        if self.events_day != today {
+            // The popover follows midnight back to the new today
+            self.selected_day = today;
+        }
+        self.events_day = today;
+
+        let mut days = vec![(today, "Today".to_string())];
+        if self.selected_day != today {
+            days.push((
+                self.selected_day,
+                self.selected_day.format("%A, %B %-d").to_string(),
+            ));
+        }
+        self.events = days
+            .into_iter()
+            .map(|(day, title)| (title, self.calendar.events_on(day)))
+            .collect();
+    }
+
+    fn update_next_event(&mut self) {
+        let within = Duration::minutes(self.calendar_config.remind_before.into());
+        self.next_event = if within.is_zero() {
+            None
+        } else {
+            self.calendar.next_event(Local::now(), within)
+        };
+    }
+
+    /// Combines the schedule, fullscreen state and manual toggle into the Do Not Disturb state
+    fn update_dnd(&mut self) {
+        let config = &self.notifications_config;
+        let auto = if config
+            .dnd_schedule()
+            .is_some_and(|schedule| in_schedule(schedule, Local::now().time()))
+        {
+            Some(DndReason::Scheduled)
+        } else if config.dnd_when_fullscreen && has_fullscreen_window(self.monitor.as_deref()) {
+            Some(DndReason::Fullscreen)
+        } else {
+            None
+        };
+        if auto.is_some() != self.dnd_auto.is_some() {
+            self.dnd_override = None;
+        }
+        self.dnd_auto = auto;
+
+        let dnd = match self.dnd_override {
+            Some(true) => Some(DndReason::Manual),
+            Some(false) => None,
+            None => auto,
+        };
+        if dnd != self.dnd {
+            self.dnd = dnd;
+            self.config_window
+                .emit(ConfMessage::DoNotDisturbChanged(dnd));
+        }
+    }
+}
+
+fn connected_monitors() -> Vec<gtk::gdk::Monitor> {
+    let display = gtk::gdk::Display::default().expect("Could not get default display");
+    let monitor_list = display.monitors();
+    (0..monitor_list.n_items())
+        .filter_map(|i| monitor_list.item(i)?.downcast::<gtk::gdk::Monitor>().ok())
+        .collect()
+}
+
+fn warn_missing_monitors(names: &[String]) {
+    let monitors = connected_monitors();
+    let connectors: Vec<String> = monitors
+        .iter()
+        .filter_map(|monitor| monitor.connector().map(|connector| connector.to_string()))
+        .collect();
+    let missing: Vec<&String> = names
+        .iter()
+        .filter(|name| !connectors.contains(name))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    for name in missing {
+        eprintln!(
+            "Warning: Monitor '{}' not found, its bar appears once it is connected",
+            name
+        );
+    }
+    eprintln!("Available monitors:");
+    for connector in connectors {
+        eprintln!("  - {}", connector);
+    }
+}
+
+/// Ticks right after the next second or minute boundary, so the clock never lags behind
+fn schedule_clock_tick(
+    sender: &ComponentSender<App>,
+    delay: std::time::Duration,
+) -> glib::SourceId {
+    let sender = sender.clone();
+    glib::timeout_add_local_once(delay, move || {
+        sender.input(AppMessage::ClockUpdate);
+    })
+}
+
+/// Removes a timeout unless it already ran, which `SourceId::remove` would panic on
+fn remove_pending(id: &glib::SourceId) {
+    if let Some(source) = glib::MainContext::default().find_source_by_id(id) {
+        source.destroy();
     }
 }
 
 fn setup_timers(sender: &ComponentSender<App>) {
-    // Update clock
+    // Update timer
     let sender_clone = sender.clone();
     glib::timeout_add_seconds_local(1, move || {
-        sender_clone.input(AppMessage::ClockUpdate);
+        sender_clone.input(AppMessage::TimerTick);
+        glib::ControlFlow::Continue
+    });
+
+    // Update upcoming meeting and follow midnight
+    let sender_clone = sender.clone();
+    glib::timeout_add_seconds_local(30, move || {
+        sender_clone.input(AppMessage::CalendarUpdate);
+        glib::ControlFlow::Continue
+    });
+
+    // Re-read calendars, vdirsyncer may have synced
+    let sender_clone = sender.clone();
+    glib::timeout_add_seconds_local(300, move || {
+        sender_clone.input(AppMessage::CalendarReload);
         glib::ControlFlow::Continue
     });
 
@@ -258,6 +1003,27 @@ fn setup_timers(sender: &ComponentSender<App>) {
         glib::ControlFlow::Continue
     });
 
+    // Update brightness
+    let sender_clone = sender.clone();
+    glib::timeout_add_seconds_local(1, move || {
+        sender_clone.input(AppMessage::BrightnessUpdate);
+        glib::ControlFlow::Continue
+    });
+
+    // Update power profile
+    let sender_clone = sender.clone();
+    glib::timeout_add_seconds_local(2, move || {
+        sender_clone.input(AppMessage::PowerProfileUpdate);
+        glib::ControlFlow::Continue
+    });
+
+    // Update Do Not Disturb schedule and fullscreen state
+    let sender_clone = sender.clone();
+    glib::timeout_add_seconds_local(1, move || {
+        sender_clone.input(AppMessage::DoNotDisturbUpdate);
+        glib::ControlFlow::Continue
+    });
+
     // Update connectivity status
     let sender_clone = sender.clone();
     glib::timeout_add_seconds_local(1, move || {
@@ -269,9 +1035,60 @@ fn setup_timers(sender: &ComponentSender<App>) {
 fn main() {
     // Parse arguments before GTK initializes
     let args = Args::parse();
+    if let Some(command) = args.command.clone() {
+        std::process::exit(run_command(command, args.single_monitor()));
+    }
 
     // Create app with no GTK arguments
-    let app = RelmApp::new("me.bofusland.adwbar").with_args(Vec::<String>::new());
+    let app = RelmApp::new("me.bofusland.adwbar")
+        .with_args(Vec::<String>::new())
+        .visible_on_activate(false);
 
     app.run::<App>(args);
 }
+
+/// Sends a subcommand to the running bar and prints its reply, returning the exit code.
+/// `--monitor` picks the bar that was started for that monitor alone.
+fn run_command(command: Commands, monitor: Option<&str>) -> i32 {
+    let (request, json) = match command {
+        Commands::ToggleControlCenter => (Request::ToggleControlCenter, false),
+        Commands::Reload => (Request::Reload, false),
+        Commands::Hide => (Request::Hide, false),
+        Commands::Show => (Request::Show, false),
+        Commands::Toggle => (Request::Toggle, false),
+        Commands::SetModuleText { id, text } => (Request::SetModuleText { id, text }, false),
+        Commands::Status { json } => (Request::Status, json),
+    };
+    match ipc::send(&request, monitor) {
+        Ok(Reply::Ok) => 0,
+        Ok(Reply::Status(status)) if json => {
+            println!("{}", serde_json::to_string(&status).unwrap_or_default());
+            0
+        }
+        Ok(Reply::Status(status)) => {
+            println!("visible: {}", status.visible);
+            println!("control center: {}", status.control_center_visible);
+            println!("clock: {}", status.clock);
+            println!("do not disturb: {}", status.do_not_disturb);
+            println!("unread notifications: {}", status.unread_notifications);
+            if let Some(timer) = status.timer {
+                println!("timer: {}", timer);
+            }
+            if let Some(media) = status.media {
+                println!("media: {}", media);
+            }
+            for module in status.modules {
+                println!("{}: {}", module.id, module.text);
+            }
+            0
+        }
+        Ok(Reply::Error(e)) => {
+            eprintln!("{}", e);
+            1
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
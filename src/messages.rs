@@ -1,11 +1,70 @@
+use crate::audio::{AudioDevices, DeviceKind};
+use crate::bar::BarState;
+use crate::bluetooth::{AgentRequest, DeviceAction};
+use crate::brightness::BacklightKind;
+use crate::connectivity::ToggleError;
+use crate::custom::CustomOutput;
+use crate::ipc::{Reply, Request};
+use crate::mpris::Player;
+use crate::notifications::{CloseReason, DndReason, Notification};
+use crate::session::SessionAction;
+use chrono::NaiveDate;
+
 /// Messages for the main application component
 #[derive(Debug)]
 pub enum AppMessage {
+    MonitorsChanged,
     ClockUpdate,
+    ClockToggleFormat,
+    TimerTick,
+    StartCountdown(u32),
+    StartPomodoro,
+    StartStopwatch,
+    TimerPauseResume,
+    TimerStop,
+    CalendarReload,
+    CalendarUpdate,
+    CalendarDaySelected(NaiveDate),
     WindowListUpdate,
     SystemInfoUpdate,
     ConnectivityUpdate,
+    VpnUpdate(bool),
+    VolumeUpdate(AudioDevices),
+    VolumeScroll(f64),
+    VolumeMuteToggle,
+    BrightnessUpdate,
+    BrightnessScroll(f64),
+    PowerProfileUpdate,
+    MediaUpdate(Vec<Player>),
+    MediaPlayPause,
+    MediaScroll(f64),
     SystemInfoClicked,
+    CustomModuleUpdate(usize, CustomOutput),
+    NotificationReceived(Notification),
+    /// Removes a notification from popups and history and tells its sender why
+    CloseNotification(u32, CloseReason),
+    InvokeNotificationAction(u32, String),
+    DoNotDisturbUpdate,
+    ToggleDoNotDisturb,
+    SetDoNotDisturb(bool),
+    OverviewChanged(bool),
+    FullscreenUpdate,
+    /// A command from `adwbar <subcommand>`, answered through the sender
+    IpcRequest(Request, relm4::Sender<Reply>),
+}
+
+/// Messages for each bar
+#[derive(Debug)]
+pub enum BarMessage {
+    Update(Box<BarState>),
+    PointerEntered,
+    PointerLeft,
+    /// A popover of the bar opened or closed
+    MenuToggled(bool),
+    /// The auto-hide delay passed since the pointer left
+    HideTimeout,
+    /// Whether the active workspace on the bar's output has a fullscreen window
+    Fullscreen(bool),
 }
 
 /// messages for config window component
@@ -14,4 +73,71 @@ pub enum ConfMessage {
     UpdateStatus,
     ToggleWifi(bool),
     ToggleBluetooth(bool),
+    ToggleAirplaneMode(bool),
+    SetVolume(DeviceKind, u32),
+    ToggleMute(DeviceKind),
+    SelectAudioDevice(DeviceKind, u32),
+    AudioChanged(AudioDevices),
+    /// A change to a sound device failed, with what the toast should say
+    AudioChangeFailed(String, ToggleError),
+    SetBrightness(BacklightKind, u32),
+    SetPowerProfile(String),
+    PlayersChanged(Vec<Player>),
+    SelectPlayer(u32),
+    /// A player control failed, with what the toast should say
+    MediaControlDone(&'static str, Result<(), ToggleError>),
+    MediaPrevious,
+    MediaPlayPause,
+    MediaNext,
+    /// Seek position in seconds
+    SeekMedia(f64),
+    RequestSessionAction(SessionAction),
+    PerformSessionAction(SessionAction),
+    SessionActionDone(SessionAction, Result<(), ToggleError>),
+    ToggleVpn(String, bool),
+    /// NetworkManager reported a changed profile or connection
+    NetworksChanged,
+    AdaptersChanged,
+    SelectAdapter(u32),
+    ToggleDiscovery(bool),
+    ToggleBluetoothDevice(String),
+    BluetoothDeviceDone(String, DeviceAction, Result<(), ToggleError>),
+    BluetoothConfirm {
+        device: String,
+        request: AgentRequest,
+        reply: relm4::Sender<bool>,
+    },
+    BluetoothDisplayCode {
+        device: String,
+        code: String,
+    },
+    BluetoothAgentCancel,
+    NotificationReceived(Notification),
+    RemoveNotification(u32),
+    ClearNotifications,
+    DoNotDisturbChanged(Option<DndReason>),
+}
+
+/// Output of the config window towards the bar
+#[derive(Debug)]
+pub enum ConfOutput {
+    DismissNotification(u32),
+    InvokeNotificationAction(u32, String),
+    SetDoNotDisturb(bool),
+}
+
+/// Messages for the notification popups
+#[derive(Debug)]
+pub enum PopupMessage {
+    Show(Notification),
+    Close(u32),
+    /// Timer for the popup with the given id and serial ran out
+    Expire(u32, u64),
+}
+
+/// Output of the notification popups towards the bar
+#[derive(Debug)]
+pub enum PopupOutput {
+    Dismissed(u32),
+    ActionInvoked(u32, String),
 }
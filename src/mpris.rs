@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use zbus::blocking::Connection;
+use zbus::zvariant::{ObjectPath, OwnedValue};
+
+const PREFIX: &str = "org.mpris.MediaPlayer2.";
+const PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Player picked in the control center, preferred over the one currently playing
+static SELECTED_PLAYER: Mutex<Option<String>> = Mutex::new(None);
+
+/// How long a player may take to answer before it is skipped until the next read
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A media player found on the session bus
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub bus_name: String,
+    pub identity: String,
+    /// "Playing", "Paused" or "Stopped"
+    pub status: String,
+    pub title: String,
+    pub artist: String,
+    pub art_url: Option<String>,
+    pub track_id: Option<String>,
+    /// Track length and position in microseconds
+    pub length: i64,
+    pub position: i64,
+    pub can_seek: bool,
+}
+
+impl Player {
+    pub fn is_playing(&self) -> bool {
+        self.status == "Playing"
+    }
+
+    /// "Artist – Title", or whichever of the two is known
+    pub fn now_playing(&self) -> String {
+        match (self.artist.is_empty(), self.title.is_empty()) {
+            (false, false) => format!("{} – {}", self.artist, self.title),
+            (true, false) => self.title.clone(),
+            (false, true) => self.artist.clone(),
+            (true, true) => self.identity.clone(),
+        }
+    }
+
+    /// Local file of the album art; remote art is not downloaded
+    pub fn art_path(&self) -> Option<String> {
+        percent_decode(self.art_url.as_deref()?.strip_prefix("file://")?)
+    }
+}
+
+/// Turns the %XX escapes of a URI back into bytes, None if they don't form UTF-8
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn player_proxy<'a>(
+    conn: &Connection,
+    bus_name: &'a str,
+) -> zbus::Result<zbus::blocking::Proxy<'a>> {
+    zbus::blocking::Proxy::new(conn, bus_name, PATH, PLAYER_INTERFACE)
+}
+
+fn read_player(conn: &Connection, bus_name: &str) -> Option<Player> {
+    let proxy = player_proxy(conn, bus_name).ok()?;
+    let metadata: HashMap<String, OwnedValue> = proxy.get_property("Metadata").ok()?;
+    let text = |key: &str| {
+        metadata
+            .get(key)
+            .and_then(|value| <&str>::try_from(value).ok())
+            .map(str::to_string)
+    };
+
+    let artist = metadata
+        .get("xesam:artist")
+        .and_then(|value| value.try_clone().ok())
+        .and_then(|value| Vec::<String>::try_from(value).ok())
+        .unwrap_or_default()
+        .join(", ");
+    // Some players send the track id as a plain string instead of an object path
+    let track_id = metadata.get("mpris:trackid").and_then(|value| {
+        <&ObjectPath>::try_from(value)
+            .map(|path| path.to_string())
+            .ok()
+            .or_else(|| <&str>::try_from(value).ok().map(str::to_string))
+    });
+    let length = metadata
+        .get("mpris:length")
+        .and_then(|value| {
+            i64::try_from(value)
+                .ok()
+                .or_else(|| u64::try_from(value).ok().map(|length| length as i64))
+        })
+        .unwrap_or(0);
+
+    let identity = zbus::blocking::Proxy::new(conn, bus_name, PATH, "org.mpris.MediaPlayer2")
+        .and_then(|proxy| proxy.get_property::<String>("Identity"))
+        .unwrap_or_else(|_| bus_name.trim_start_matches(PREFIX).to_string());
+
+    Some(Player {
+        bus_name: bus_name.to_string(),
+        identity,
+        status: proxy.get_property("PlaybackStatus").unwrap_or_default(),
+        title: text("xesam:title").unwrap_or_default(),
+        artist,
+        art_url: text("mpris:artUrl"),
+        track_id,
+        length,
+        position: proxy.get_property("Position").unwrap_or(0),
+        can_seek: proxy.get_property("CanSeek").unwrap_or(false),
+    })
+}
+
+/// Reads every org.mpris.MediaPlayer2.* name on the session bus, sorted by name
+fn read_players(conn: &Connection) -> Vec<Player> {
+    let Ok(dbus) = zbus::blocking::fdo::DBusProxy::new(conn) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = dbus
+        .list_names()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|name| name.to_string())
+        .filter(|name| name.starts_with(PREFIX))
+        .collect();
+    names.sort();
+
+    names
+        .iter()
+        .filter_map(|name| read_player(conn, name))
+        .collect()
+}
+
+/// Wakes the thread started by [`watch_players`] to read the players again right away
+#[derive(Debug, Clone)]
+pub struct PlayersWatcher(mpsc::Sender<()>);
+
+impl PlayersWatcher {
+    /// Also reports the players when nothing changed, e.g. after picking another one
+    pub fn refresh(&self) {
+        let _ = self.0.send(());
+    }
+}
+
+/// Reads the players on a thread every second, calling `on_change` whenever they changed.
+/// Players are slow to answer at times, or hang, which must not stall the bar.
+pub fn watch_players(on_change: impl Fn(Vec<Player>) + Send + 'static) -> PlayersWatcher {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let conn = match zbus::blocking::connection::Builder::session()
+            .and_then(|builder| builder.method_timeout(READ_TIMEOUT).build())
+        {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!(
+                    "Failed to connect to the session bus for media players: {}",
+                    e
+                );
+                return;
+            }
+        };
+        let mut last = None;
+        let mut refresh = true;
+        loop {
+            let players = read_players(&conn);
+            if refresh || last.as_ref() != Some(&players) {
+                on_change(players.clone());
+                last = Some(players);
+            }
+            refresh = match receiver.recv_timeout(Duration::from_secs(1)) {
+                Ok(()) => true,
+                Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+        }
+    });
+    PlayersWatcher(sender)
+}
+
+/// The selected player if it is still around, otherwise the first one playing
+pub fn active_player(players: &[Player]) -> Option<&Player> {
+    let selected = SELECTED_PLAYER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    players
+        .iter()
+        .find(|player| Some(&player.bus_name) == selected.as_ref())
+        .or_else(|| players.iter().find(|player| player.is_playing()))
+        .or_else(|| players.first())
+}
+
+pub fn select_player(bus_name: &str) {
+    *SELECTED_PLAYER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(bus_name.to_string());
+}
+
+fn call(conn: &Connection, bus_name: &str, method: &str) -> zbus::Result<()> {
+    player_proxy(conn, bus_name)?.call(method, &())
+}
+
+/// Blocks until the player answers, so call it off the main thread
+pub fn play_pause(player: &Player) -> zbus::Result<()> {
+    call(&Connection::session()?, &player.bus_name, "PlayPause")
+}
+
+pub fn next(player: &Player) -> zbus::Result<()> {
+    call(&Connection::session()?, &player.bus_name, "Next")
+}
+
+pub fn previous(player: &Player) -> zbus::Result<()> {
+    call(&Connection::session()?, &player.bus_name, "Previous")
+}
+
+/// Jumps to the position in microseconds; players ignore this without the current track id
+pub fn seek(player: &Player, position: i64) -> zbus::Result<()> {
+    let Some(track_id) = &player.track_id else {
+        return Ok(());
+    };
+    let conn = Connection::session()?;
+    player_proxy(&conn, &player.bus_name)?.call(
+        "SetPosition",
+        &(ObjectPath::try_from(track_id.as_str())?, position),
+    )
+}
+
+/// Formats microseconds as m:ss
+pub fn format_time(microseconds: i64) -> String {
+    let seconds = microseconds.max(0) / 1_000_000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+    use std::sync::Arc;
+    use zbus::zvariant::Value;
+
+    const BUS_NAME: &str = "org.mpris.MediaPlayer2.fake";
+
+    /// Answers like a paused player and records the methods called on it
+    #[derive(Default)]
+    struct FakePlayer {
+        calls: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl FakePlayer {
+        fn play_pause(&self) {
+            self.calls.lock().unwrap().push("PlayPause");
+        }
+
+        fn next(&self) {
+            self.calls.lock().unwrap().push("Next");
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            let entries = [
+                ("xesam:title", Value::from("Song")),
+                ("xesam:artist", Value::from(vec!["Band", "Guest"])),
+                ("mpris:length", Value::from(180_000_000i64)),
+                (
+                    "mpris:trackid",
+                    Value::from(ObjectPath::from_static_str_unchecked("/track/1")),
+                ),
+                ("mpris:artUrl", Value::from("file:///tmp/My%20Album.jpg")),
+            ];
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.try_into().unwrap()))
+                .collect()
+        }
+
+        #[zbus(property)]
+        fn playback_status(&self) -> &str {
+            "Paused"
+        }
+
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            42_000_000
+        }
+
+        #[zbus(property)]
+        fn can_seek(&self) -> bool {
+            true
+        }
+    }
+
+    struct FakeIdentity;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2")]
+    impl FakeIdentity {
+        #[zbus(property)]
+        fn identity(&self) -> &str {
+            "Fake Player"
+        }
+    }
+
+    /// Serves the fake player on one end of a private peer-to-peer bus and returns the other
+    fn connect(player: FakePlayer) -> (Connection, Connection) {
+        let (server, client) = UnixStream::pair().unwrap();
+        let server = std::thread::spawn(move || {
+            zbus::blocking::connection::Builder::unix_stream(server)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at(PATH, player)
+                .unwrap()
+                .serve_at(PATH, FakeIdentity)
+                .unwrap()
+                .build()
+                .unwrap()
+        });
+        let client = zbus::blocking::connection::Builder::unix_stream(client)
+            .p2p()
+            .build()
+            .unwrap();
+        (server.join().unwrap(), client)
+    }
+
+    #[test]
+    fn reads_the_player_metadata() {
+        let (_server, conn) = connect(FakePlayer::default());
+        let player = read_player(&conn, BUS_NAME).unwrap();
+        assert_eq!(player.identity, "Fake Player");
+        assert_eq!(player.now_playing(), "Band, Guest – Song");
+        assert!(!player.is_playing());
+        assert_eq!(player.track_id.as_deref(), Some("/track/1"));
+        assert_eq!(player.length, 180_000_000);
+        assert_eq!(player.position, 42_000_000);
+        assert!(player.can_seek);
+        assert_eq!(player.art_path().as_deref(), Some("/tmp/My Album.jpg"));
+    }
+
+    #[test]
+    fn controls_reach_the_player() {
+        let fake = FakePlayer::default();
+        let calls = fake.calls.clone();
+        let (_server, conn) = connect(fake);
+        call(&conn, BUS_NAME, "PlayPause").unwrap();
+        call(&conn, BUS_NAME, "Next").unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["PlayPause", "Next"]);
+    }
+
+    #[test]
+    fn reports_unsupported_controls() {
+        let (_server, conn) = connect(FakePlayer::default());
+        assert!(call(&conn, BUS_NAME, "Previous").is_err());
+    }
+
+    #[test]
+    fn decodes_art_paths() {
+        let player = |url: &str| Player {
+            bus_name: BUS_NAME.to_string(),
+            identity: String::new(),
+            status: String::new(),
+            title: String::new(),
+            artist: String::new(),
+            art_url: Some(url.to_string()),
+            track_id: None,
+            length: 0,
+            position: 0,
+            can_seek: false,
+        };
+        assert_eq!(
+            player("file:///home/me/Caf%C3%A9%20Music/cover.png").art_path(),
+            Some("/home/me/Café Music/cover.png".to_string())
+        );
+        assert_eq!(
+            player("file:///tmp/100%.png").art_path(),
+            Some("/tmp/100%.png".to_string())
+        );
+        assert_eq!(player("https://example.com/cover.png").art_path(), None);
+    }
+}
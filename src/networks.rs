@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+use zbus::{MatchRule, message};
+
+const NM_DEVICE_TYPE_ETHERNET: u32 = 1;
+const NM_DEVICE_STATE_UNAVAILABLE: u32 = 20;
+const NM_DEVICE_STATE_ACTIVATED: u32 = 100;
+
+/// An ethernet device managed by NetworkManager
+#[derive(Debug, Clone, PartialEq)]
+pub struct EthernetDevice {
+    pub interface: String,
+    pub connected: bool,
+    pub status: String,
+    pub ip: Option<String>,
+}
+
+/// A saved VPN or WireGuard connection profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct VpnConnection {
+    pub id: String,
+    pub path: String,
+    pub wireguard: bool,
+    /// Path of the active connection while the profile is up
+    pub active: Option<String>,
+}
+
+/// What a saved profile says about itself, only read again when the profile changes
+#[derive(Debug, Clone)]
+struct VpnProfile {
+    id: String,
+    wireguard: bool,
+}
+
+/// Saved profiles by path, `None` for the ones that are not VPNs. Profiles are read once,
+/// [`watch_connections`] drops the ones NetworkManager reports as changed.
+static PROFILES: Mutex<Option<HashMap<String, Option<VpnProfile>>>> = Mutex::new(None);
+
+fn cached_profiles() -> MutexGuard<'static, Option<HashMap<String, Option<VpnProfile>>>> {
+    PROFILES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn nm_proxy<'a>(
+    conn: &Connection,
+    path: &'a str,
+    interface: &'a str,
+) -> zbus::Result<zbus::blocking::Proxy<'a>> {
+    zbus::blocking::Proxy::new(conn, "org.freedesktop.NetworkManager", path, interface)
+}
+
+/// Lists ethernet devices with their link state and IPv4 address
+pub fn read_ethernet_devices() -> Vec<EthernetDevice> {
+    let Ok(conn) = Connection::system() else {
+        return Vec::new();
+    };
+    let Ok(proxy) = nm_proxy(
+        &conn,
+        "/org/freedesktop/NetworkManager",
+        "org.freedesktop.NetworkManager",
+    ) else {
+        return Vec::new();
+    };
+    let Ok(devices) = proxy.get_property::<Vec<OwnedObjectPath>>("Devices") else {
+        return Vec::new();
+    };
+
+    let mut ethernet = Vec::new();
+    for device in devices {
+        let Ok(device) = nm_proxy(
+            &conn,
+            device.as_str(),
+            "org.freedesktop.NetworkManager.Device",
+        ) else {
+            continue;
+        };
+        if !matches!(
+            device.get_property::<u32>("DeviceType"),
+            Ok(NM_DEVICE_TYPE_ETHERNET)
+        ) {
+            continue;
+        }
+
+        let interface = device
+            .get_property::<String>("Interface")
+            .unwrap_or_default();
+        let state = device.get_property::<u32>("State").unwrap_or(0);
+        let status = match state {
+            NM_DEVICE_STATE_ACTIVATED => "Connected",
+            NM_DEVICE_STATE_UNAVAILABLE => "Cable unplugged",
+            s if s > NM_DEVICE_STATE_UNAVAILABLE && s < NM_DEVICE_STATE_ACTIVATED => "Connecting",
+            _ => "Disconnected",
+        };
+        let ip = device
+            .get_property::<OwnedObjectPath>("Ip4Config")
+            .ok()
+            .and_then(|path| read_ipv4_address(&conn, path.as_str()));
+
+        ethernet.push(EthernetDevice {
+            interface,
+            connected: state == NM_DEVICE_STATE_ACTIVATED,
+            status: status.to_string(),
+            ip,
+        });
+    }
+    ethernet.sort_by(|a, b| a.interface.cmp(&b.interface));
+    ethernet
+}
+
+fn read_ipv4_address(conn: &Connection, path: &str) -> Option<String> {
+    if path == "/" {
+        return None;
+    }
+    let proxy = nm_proxy(conn, path, "org.freedesktop.NetworkManager.IP4Config").ok()?;
+    let addresses = proxy
+        .get_property::<Vec<HashMap<String, OwnedValue>>>("AddressData")
+        .ok()?;
+    let address = addresses.first()?;
+    let ip = <&str>::try_from(address.get("address")?).ok()?;
+    match address.get("prefix").and_then(|p| u32::try_from(p).ok()) {
+        Some(prefix) => Some(format!("{}/{}", ip, prefix)),
+        None => Some(ip.to_string()),
+    }
+}
+
+/// Lists saved VPN and WireGuard connections and whether they are active
+pub fn read_vpn_connections() -> Vec<VpnConnection> {
+    let Ok(conn) = Connection::system() else {
+        return Vec::new();
+    };
+    let Ok(settings) = nm_proxy(
+        &conn,
+        "/org/freedesktop/NetworkManager/Settings",
+        "org.freedesktop.NetworkManager.Settings",
+    ) else {
+        return Vec::new();
+    };
+    let Ok(profiles) = settings.call::<_, _, Vec<OwnedObjectPath>>("ListConnections", &()) else {
+        return Vec::new();
+    };
+
+    let active = read_active_connections(&conn);
+    let mut cache = cached_profiles();
+    let cache = cache.get_or_insert_with(HashMap::new);
+    cache.retain(|path, _| profiles.iter().any(|profile| profile.as_str() == path));
+
+    let mut vpns = Vec::new();
+    for profile in profiles {
+        let vpn = match cache.get(profile.as_str()) {
+            Some(vpn) => vpn.clone(),
+            // Left out of the cache on errors so the next read tries again
+            None => match read_vpn_profile(&conn, profile.as_str()) {
+                Ok(vpn) => {
+                    cache.insert(profile.to_string(), vpn.clone());
+                    vpn
+                }
+                Err(_) => continue,
+            },
+        };
+        let Some(VpnProfile { id, wireguard }) = vpn else {
+            continue;
+        };
+
+        vpns.push(VpnConnection {
+            id,
+            path: profile.to_string(),
+            wireguard,
+            active: active.get(profile.as_str()).cloned(),
+        });
+    }
+    vpns.sort_by(|a, b| a.id.cmp(&b.id));
+    vpns
+}
+
+/// Reads a saved profile's settings, `None` if it is not a VPN or WireGuard connection
+fn read_vpn_profile(conn: &Connection, path: &str) -> zbus::Result<Option<VpnProfile>> {
+    let proxy = nm_proxy(
+        conn,
+        path,
+        "org.freedesktop.NetworkManager.Settings.Connection",
+    )?;
+    let settings =
+        proxy.call::<_, _, HashMap<String, HashMap<String, OwnedValue>>>("GetSettings", &())?;
+    let Some(connection) = settings.get("connection") else {
+        return Ok(None);
+    };
+    let kind = connection
+        .get("type")
+        .and_then(|t| <&str>::try_from(t).ok())
+        .unwrap_or_default();
+    if kind != "vpn" && kind != "wireguard" {
+        return Ok(None);
+    }
+    let id = connection
+        .get("id")
+        .and_then(|id| <&str>::try_from(id).ok())
+        .unwrap_or_default()
+        .to_string();
+    Ok(Some(VpnProfile {
+        id,
+        wireguard: kind == "wireguard",
+    }))
+}
+
+/// Calls `on_change` on a thread whenever a profile is added, removed or edited, or a
+/// connection comes up or goes down, so nothing has to poll NetworkManager
+pub fn watch_connections(on_change: impl Fn() + Send + 'static) {
+    std::thread::spawn(move || {
+        let Ok(conn) = Connection::system() else {
+            return;
+        };
+        let Ok(signals) = MatchRule::builder()
+            .msg_type(message::Type::Signal)
+            .sender("org.freedesktop.NetworkManager")
+            .and_then(|rule| rule.path_namespace("/org/freedesktop/NetworkManager"))
+            .and_then(|rule| MessageIterator::for_match_rule(rule.build(), &conn, None))
+        else {
+            return;
+        };
+
+        on_change();
+        for signal in signals.flatten() {
+            let header = signal.header();
+            let (Some(path), Some(interface), Some(member)) =
+                (header.path(), header.interface(), header.member())
+            else {
+                continue;
+            };
+            let changed = match (interface.as_str(), member.as_str()) {
+                ("org.freedesktop.NetworkManager.Settings", "NewConnection")
+                | ("org.freedesktop.NetworkManager.Settings", "ConnectionRemoved") => true,
+                ("org.freedesktop.NetworkManager.Settings.Connection", "Updated") => {
+                    forget_profile(path.as_str());
+                    true
+                }
+                ("org.freedesktop.DBus.Properties", "PropertiesChanged") => {
+                    match signal
+                        .body()
+                        .deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+                    {
+                        Ok((interface, _, _))
+                            if interface
+                                == "org.freedesktop.NetworkManager.Settings.Connection" =>
+                        {
+                            forget_profile(path.as_str());
+                            true
+                        }
+                        Ok((interface, changed, _))
+                            if interface == "org.freedesktop.NetworkManager" =>
+                        {
+                            changed.contains_key("ActiveConnections")
+                        }
+                        _ => false,
+                    }
+                }
+                _ => false,
+            };
+            if changed {
+                on_change();
+            }
+        }
+    });
+}
+
+fn forget_profile(path: &str) {
+    if let Some(cache) = cached_profiles().as_mut() {
+        cache.remove(path);
+    }
+}
+
+/// Maps settings profile paths to the active connection using them
+fn read_active_connections(conn: &Connection) -> HashMap<String, String> {
+    let mut active = HashMap::new();
+    let Ok(proxy) = nm_proxy(
+        conn,
+        "/org/freedesktop/NetworkManager",
+        "org.freedesktop.NetworkManager",
+    ) else {
+        return active;
+    };
+    let Ok(connections) = proxy.get_property::<Vec<OwnedObjectPath>>("ActiveConnections") else {
+        return active;
+    };
+
+    for connection in connections {
+        if let Ok(proxy) = nm_proxy(
+            conn,
+            connection.as_str(),
+            "org.freedesktop.NetworkManager.Connection.Active",
+        ) && let Ok(profile) = proxy.get_property::<OwnedObjectPath>("Connection")
+        {
+            active.insert(profile.to_string(), connection.to_string());
+        }
+    }
+    active
+}
+
+/// Returns true if any VPN or WireGuard connection is currently up
+pub fn is_vpn_active() -> bool {
+    let Ok(conn) = Connection::system() else {
+        return false;
+    };
+    let Ok(proxy) = nm_proxy(
+        &conn,
+        "/org/freedesktop/NetworkManager",
+        "org.freedesktop.NetworkManager",
+    ) else {
+        return false;
+    };
+    let Ok(connections) = proxy.get_property::<Vec<OwnedObjectPath>>("ActiveConnections") else {
+        return false;
+    };
+
+    connections.iter().any(|connection| {
+        nm_proxy(
+            &conn,
+            connection.as_str(),
+            "org.freedesktop.NetworkManager.Connection.Active",
+        )
+        .and_then(|proxy| proxy.get_property::<String>("Type"))
+        .is_ok_and(|kind| kind == "vpn" || kind == "wireguard")
+    })
+}
+
+/// Activates or deactivates a saved VPN connection
+pub fn set_vpn_active(vpn: &VpnConnection, active: bool) -> zbus::Result<()> {
+    let conn = Connection::system()?;
+    let proxy = nm_proxy(
+        &conn,
+        "/org/freedesktop/NetworkManager",
+        "org.freedesktop.NetworkManager",
+    )?;
+    let root = ObjectPath::from_static_str_unchecked("/");
+
+    if active {
+        let profile = ObjectPath::try_from(vpn.path.as_str())?;
+        proxy.call::<_, _, OwnedObjectPath>("ActivateConnection", &(&profile, &root, &root))?;
+    } else if let Some(path) = &vpn.active {
+        let active_connection = ObjectPath::try_from(path.as_str())?;
+        proxy.call::<_, _, ()>("DeactivateConnection", &(&active_connection,))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use crate::config::NotificationsConfig;
+use crate::messages::{PopupMessage, PopupOutput};
+use crate::notifications::{Notification, URGENCY_CRITICAL};
+use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use relm4::adw::glib;
+use relm4::gtk::prelude::*;
+use relm4::prelude::*;
+use std::time::Duration;
+
+struct Popup {
+    id: u32,
+    /// Distinguishes a replaced notification from the one an old timer was started for
+    serial: u64,
+    card: gtk::Box,
+}
+
+/// Notification popups stacked in the top right corner, below the bar
+pub struct NotificationPopups {
+    config: NotificationsConfig,
+    popups: Vec<Popup>,
+    next_serial: u64,
+    container: gtk::Box,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for NotificationPopups {
+    type Init = NotificationsConfig;
+    type Input = PopupMessage;
+    type Output = PopupOutput;
+
+    view! {
+        gtk::Window {
+            add_css_class: "notification-popups",
+            set_default_size: (360, -1),
+            #[watch]
+            set_visible: !model.popups.is_empty(),
+
+            #[local_ref]
+            container -> gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 8,
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Anchored without an exclusive zone of its own, so it is placed below the bar
+        root.init_layer_shell();
+        root.set_layer(Layer::Overlay);
+        root.set_anchor(Edge::Top, true);
+        root.set_anchor(Edge::Right, true);
+        root.set_margin(Edge::Top, 8);
+        root.set_margin(Edge::Right, 8);
+
+        let model = Self {
+            config,
+            popups: Vec::new(),
+            next_serial: 0,
+            container: gtk::Box::new(gtk::Orientation::Vertical, 8),
+        };
+
+        let container = &model.container;
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            PopupMessage::Show(notification) => {
+                self.remove(notification.id);
+                self.next_serial += 1;
+                let serial = self.next_serial;
+
+                if let Some(timeout) = self.timeout(&notification) {
+                    let sender = sender.clone();
+                    let id = notification.id;
+                    glib::timeout_add_local_once(timeout, move || {
+                        sender.input(PopupMessage::Expire(id, serial));
+                    });
+                }
+
+                let card = popup_card(&notification, &sender);
+                self.container.prepend(&card);
+                self.popups.push(Popup {
+                    id: notification.id,
+                    serial,
+                    card,
+                });
+            }
+            PopupMessage::Close(id) => self.remove(id),
+            PopupMessage::Expire(id, serial) => {
+                // Expired popups stay in the history, so the sender is not told
+                if self
+                    .popups
+                    .iter()
+                    .any(|popup| popup.id == id && popup.serial == serial)
+                {
+                    self.remove(id);
+                }
+            }
+        }
+    }
+}
+
+impl NotificationPopups {
+    fn remove(&mut self, id: u32) {
+        if let Some(index) = self.popups.iter().position(|popup| popup.id == id) {
+            let popup = self.popups.remove(index);
+            self.container.remove(&popup.card);
+        }
+    }
+
+    /// How long the popup stays up, None for critical notifications and those asking to persist
+    fn timeout(&self, notification: &Notification) -> Option<Duration> {
+        if notification.urgency == URGENCY_CRITICAL {
+            return None;
+        }
+        match notification.expire_timeout {
+            0 => None,
+            ms if ms < 0 => Some(Duration::from_secs(self.config.popup_timeout.into())),
+            ms => Some(Duration::from_millis(ms as u64)),
+        }
+    }
+}
+
+fn popup_card(
+    notification: &Notification,
+    sender: &ComponentSender<NotificationPopups>,
+) -> gtk::Box {
+    let id = notification.id;
+    let card = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    card.add_css_class("card");
+    card.add_css_class("notification-popup");
+    if notification.urgency == URGENCY_CRITICAL {
+        card.add_css_class("critical");
+    }
+
+    let header = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    header.append(&notification.image());
+
+    let text = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    text.set_hexpand(true);
+    let summary = gtk::Label::new(Some(&notification.summary));
+    summary.add_css_class("heading");
+    summary.set_xalign(0.0);
+    summary.set_wrap(true);
+    text.append(&summary);
+    if !notification.body.is_empty() {
+        let body = gtk::Label::new(Some(&notification.body));
+        body.set_xalign(0.0);
+        body.set_wrap(true);
+        body.set_lines(4);
+        body.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        text.append(&body);
+    }
+    header.append(&text);
+
+    let close = gtk::Button::from_icon_name("window-close-symbolic");
+    close.add_css_class("flat");
+    close.add_css_class("circular");
+    close.set_valign(gtk::Align::Start);
+    close.connect_clicked({
+        let sender = sender.clone();
+        move |_| {
+            sender.input(PopupMessage::Close(id));
+            sender.output(PopupOutput::Dismissed(id)).ok();
+        }
+    });
+    header.append(&close);
+    card.append(&header);
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    buttons.set_homogeneous(true);
+    for (key, label) in &notification.actions {
+        if key == "default" {
+            continue;
+        }
+        let button = gtk::Button::with_label(label);
+        let sender = sender.clone();
+        let key = key.clone();
+        button.connect_clicked(move |_| {
+            sender.input(PopupMessage::Close(id));
+            sender
+                .output(PopupOutput::ActionInvoked(id, key.clone()))
+                .ok();
+        });
+        buttons.append(&button);
+    }
+    if buttons.first_child().is_some() {
+        card.append(&buttons);
+    }
+
+    // Clicking the notification itself runs its default action
+    if notification.actions.iter().any(|(key, _)| key == "default") {
+        let click = gtk::GestureClick::new();
+        let sender = sender.clone();
+        click.connect_released(move |_, _, _, _| {
+            sender.input(PopupMessage::Close(id));
+            sender
+                .output(PopupOutput::ActionInvoked(id, "default".to_string()))
+                .ok();
+        });
+        card.add_controller(click);
+    }
+
+    card
+}
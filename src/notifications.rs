@@ -0,0 +1,220 @@
+use crate::messages::AppMessage;
+use chrono::{DateTime, Local, NaiveTime};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedValue;
+
+const PATH: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+pub const URGENCY_NORMAL: u8 = 1;
+pub const URGENCY_CRITICAL: u8 = 2;
+
+/// Why Do Not Disturb is currently on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DndReason {
+    Manual,
+    Scheduled,
+    Fullscreen,
+}
+
+impl DndReason {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            DndReason::Manual => "Popups are hidden",
+            DndReason::Scheduled => "Popups are hidden during the scheduled hours",
+            DndReason::Fullscreen => "Popups are hidden while an app is fullscreen",
+        }
+    }
+}
+
+/// Whether `now` falls in the period, which wraps around midnight when `from` is after `until`
+pub fn in_schedule((from, until): (NaiveTime, NaiveTime), now: NaiveTime) -> bool {
+    if from <= until {
+        from <= now && now < until
+    } else {
+        now >= from || now < until
+    }
+}
+
+/// Reasons passed with the NotificationClosed signal. Expired (1) is never sent since
+/// expired popups stay in the history.
+#[derive(Debug, Clone, Copy)]
+pub enum CloseReason {
+    Dismissed = 2,
+    Closed = 3,
+}
+
+/// A notification received over org.freedesktop.Notifications
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    /// Icon name or file path
+    pub icon: String,
+    pub summary: String,
+    pub body: String,
+    /// (key, label) pairs; the "default" key is invoked by clicking the notification
+    pub actions: Vec<(String, String)>,
+    pub urgency: u8,
+    /// Milliseconds, -1 for the server default and 0 to never expire
+    pub expire_timeout: i32,
+    pub received: DateTime<Local>,
+}
+
+impl Notification {
+    /// Creates an image for the notification icon, which may be a name, a path or a file:// URI
+    pub fn image(&self) -> relm4::gtk::Image {
+        let icon = self.icon.strip_prefix("file://").unwrap_or(&self.icon);
+        let image = if icon.starts_with('/') {
+            relm4::gtk::Image::from_file(icon)
+        } else if icon.is_empty() {
+            relm4::gtk::Image::from_icon_name("preferences-system-notifications-symbolic")
+        } else {
+            relm4::gtk::Image::from_icon_name(icon)
+        };
+        image.set_pixel_size(32);
+        image
+    }
+}
+
+/// org.freedesktop.Notifications implementation forwarding everything to the bar
+struct NotificationServer {
+    sender: relm4::Sender<AppMessage>,
+    next_id: AtomicU32,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl NotificationServer {
+    fn get_capabilities(&self) -> Vec<&str> {
+        vec!["actions", "body", "icon-static", "persistence"]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        let id = if replaces_id != 0 {
+            replaces_id
+        } else {
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        };
+        let hint_str = |name: &str| {
+            hints
+                .get(name)
+                .and_then(|value| <&str>::try_from(value).ok())
+                .map(str::to_string)
+        };
+        let icon = if app_icon.is_empty() {
+            hint_str("image-path").unwrap_or_default()
+        } else {
+            app_icon
+        };
+        let urgency = hints
+            .get("urgency")
+            .and_then(|value| u8::try_from(value).ok())
+            .unwrap_or(URGENCY_NORMAL);
+
+        self.sender
+            .emit(AppMessage::NotificationReceived(Notification {
+                id,
+                app_name,
+                icon,
+                summary,
+                body,
+                actions: actions
+                    .chunks_exact(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect(),
+                urgency,
+                expire_timeout,
+                received: Local::now(),
+            }));
+        id
+    }
+
+    fn close_notification(&self, id: u32) {
+        self.sender
+            .emit(AppMessage::CloseNotification(id, CloseReason::Closed));
+    }
+
+    fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            "adwbar".to_string(),
+            "adwbar".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+            "1.2".to_string(),
+        )
+    }
+}
+
+/// Owns org.freedesktop.Notifications on the session bus while adwbar runs
+pub struct NotificationDaemon {
+    conn: Connection,
+}
+
+impl NotificationDaemon {
+    /// Fails when another notification daemon already owns the name
+    pub fn start(sender: relm4::Sender<AppMessage>) -> zbus::Result<Self> {
+        let server = NotificationServer {
+            sender,
+            next_id: AtomicU32::new(1),
+        };
+        let conn = zbus::blocking::connection::Builder::session()?
+            .serve_at(PATH, server)?
+            .name(INTERFACE)?
+            .build()?;
+        Ok(Self { conn })
+    }
+
+    /// Tells the sending application that one of its actions was clicked
+    pub fn invoke_action(&self, id: u32, action: &str) {
+        let _ = self.conn.emit_signal(
+            None::<&str>,
+            PATH,
+            INTERFACE,
+            "ActionInvoked",
+            &(id, action),
+        );
+    }
+
+    pub fn notify_closed(&self, id: u32, reason: CloseReason) {
+        let _ = self.conn.emit_signal(
+            None::<&str>,
+            PATH,
+            INTERFACE,
+            "NotificationClosed",
+            &(id, reason as u32),
+        );
+    }
+}
+
+/// Sends a notification through whichever daemon owns the name, which may be adwbar itself
+pub fn send_notification(summary: &str, body: &str) -> zbus::Result<()> {
+    let conn = Connection::session()?;
+    let proxy = zbus::blocking::Proxy::new(&conn, INTERFACE, PATH, INTERFACE)?;
+    let hints: HashMap<&str, zbus::zvariant::Value> = HashMap::new();
+    proxy.call::<_, _, u32>(
+        "Notify",
+        &(
+            "adwbar",
+            0u32,
+            "alarm-symbolic",
+            summary,
+            body,
+            Vec::<&str>::new(),
+            hints,
+            -1i32,
+        ),
+    )?;
+    Ok(())
+}
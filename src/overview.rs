@@ -0,0 +1,45 @@
+use crate::messages::AppMessage;
+use niri_ipc::socket::Socket;
+use niri_ipc::{Event, Request, Response};
+
+/// Follows niri's event stream on a thread and reports the overview opening and closing
+pub fn watch_overview(sender: relm4::Sender<AppMessage>) {
+    std::thread::spawn(move || {
+        let mut socket = match Socket::connect() {
+            Ok(socket) => socket,
+            Err(e) => {
+                eprintln!("Failed to connect to niri for overview events: {}", e);
+                return;
+            }
+        };
+        match socket.send(Request::EventStream) {
+            Ok(Ok(Response::Handled)) => {}
+            Ok(Err(e)) => {
+                eprintln!("niri refused the event stream: {}", e);
+                return;
+            }
+            Ok(Ok(_)) => {
+                eprintln!("Unexpected reply from niri to the event stream request");
+                return;
+            }
+            Err(e) => {
+                eprintln!("Failed to request niri's event stream: {}", e);
+                return;
+            }
+        }
+
+        let mut read_event = socket.read_events();
+        loop {
+            match read_event() {
+                Ok(Event::OverviewOpenedOrClosed { is_open }) => {
+                    sender.emit(AppMessage::OverviewChanged(is_open));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("niri event stream closed: {}", e);
+                    return;
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,110 @@
+use crate::connectivity::ToggleError;
+use std::collections::HashMap;
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedValue;
+
+/// power-profiles-daemon kept its old name for compatibility; newer releases also
+/// answer on the UPower one
+const SERVICES: [(&str, &str); 2] = [
+    ("net.hadess.PowerProfiles", "/net/hadess/PowerProfiles"),
+    (
+        "org.freedesktop.UPower.PowerProfiles",
+        "/org/freedesktop/UPower/PowerProfiles",
+    ),
+];
+
+/// Power profile state from power-profiles-daemon
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerProfiles {
+    pub active: String,
+    pub available: Vec<String>,
+    /// Why performance mode is currently throttled, empty when it is not
+    pub degraded: String,
+}
+
+impl PowerProfiles {
+    pub fn has(&self, profile: &str) -> bool {
+        self.available.iter().any(|p| p == profile)
+    }
+
+    pub fn subtitle(&self) -> String {
+        if self.degraded.is_empty() {
+            profile_label(&self.active).to_string()
+        } else {
+            format!(
+                "{} · Performance degraded: {}",
+                profile_label(&self.active),
+                degraded_label(&self.degraded)
+            )
+        }
+    }
+}
+
+fn proxy(conn: &Connection) -> Option<zbus::blocking::Proxy<'static>> {
+    SERVICES.iter().find_map(|(service, path)| {
+        let proxy = zbus::blocking::Proxy::new(conn, *service, *path, *service).ok()?;
+        proxy.get_property::<String>("ActiveProfile").ok()?;
+        Some(proxy)
+    })
+}
+
+/// Reads the active and available profiles, None when the daemon is not running
+pub fn read_power_profiles() -> Option<PowerProfiles> {
+    let conn = Connection::system().ok()?;
+    let proxy = proxy(&conn)?;
+
+    let available = proxy
+        .get_property::<Vec<HashMap<String, OwnedValue>>>("Profiles")
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|profile| {
+            <&str>::try_from(profile.get("Profile")?)
+                .ok()
+                .map(str::to_string)
+        })
+        .collect();
+
+    Some(PowerProfiles {
+        active: proxy.get_property("ActiveProfile").ok()?,
+        available,
+        degraded: proxy
+            .get_property("PerformanceDegraded")
+            .unwrap_or_default(),
+    })
+}
+
+/// Switches to the given profile, e.g. "power-saver", "balanced" or "performance"
+pub fn set_power_profile(profile: &str) -> Result<(), ToggleError> {
+    let conn = Connection::system()?;
+    let proxy = proxy(&conn).ok_or_else(|| ToggleError::DBus {
+        name: "org.freedesktop.DBus.Error.ServiceUnknown".to_string(),
+        message: "power-profiles-daemon is not running".to_string(),
+    })?;
+    proxy.set_property("ActiveProfile", profile)?;
+    Ok(())
+}
+
+pub fn profile_label(profile: &str) -> &str {
+    match profile {
+        "power-saver" => "Power Saver",
+        "balanced" => "Balanced",
+        "performance" => "Performance",
+        other => other,
+    }
+}
+
+pub fn profile_icon(profile: &str) -> &'static str {
+    match profile {
+        "power-saver" => "power-profile-power-saver-symbolic",
+        "performance" => "power-profile-performance-symbolic",
+        _ => "power-profile-balanced-symbolic",
+    }
+}
+
+fn degraded_label(reason: &str) -> &str {
+    match reason {
+        "lap-detected" => "computer is on a lap",
+        "high-operating-temperature" => "high temperature",
+        other => other,
+    }
+}
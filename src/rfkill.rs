@@ -0,0 +1,139 @@
+use std::fs;
+use std::io::{self, Write};
+
+const RFKILL_TYPE_ALL: u8 = 0;
+const RFKILL_OP_CHANGE_ALL: u8 = 3;
+
+/// A radio switch exposed by the kernel rfkill subsystem
+#[derive(Debug, Clone, PartialEq)]
+pub struct Radio {
+    pub name: String,
+    /// Radio type as reported by sysfs, e.g. "wlan" or "bluetooth"
+    pub kind: String,
+    pub soft_blocked: bool,
+    pub hard_blocked: bool,
+}
+
+/// Combined block state of every radio of one type
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RadioState {
+    pub present: bool,
+    pub soft_blocked: bool,
+    pub hard_blocked: bool,
+}
+
+impl RadioState {
+    pub fn describe(&self) -> &'static str {
+        if !self.present {
+            "Not present"
+        } else if self.hard_blocked {
+            "Blocked by hardware switch"
+        } else if self.soft_blocked {
+            "Blocked"
+        } else {
+            "On"
+        }
+    }
+}
+
+/// Human readable name for an rfkill radio type
+pub fn kind_label(kind: &str) -> &str {
+    match kind {
+        "wlan" => "Wi-Fi",
+        "bluetooth" => "Bluetooth",
+        "wwan" => "Mobile Broadband",
+        "uwb" => "Ultra-Wideband",
+        "gps" => "GPS",
+        "fm" => "FM",
+        "nfc" => "NFC",
+        other => other,
+    }
+}
+
+/// Summarizes the block state of each radio type, e.g. "Wi-Fi: On · Bluetooth: Blocked"
+pub fn describe_radios(radios: &[Radio]) -> String {
+    let mut kinds: Vec<&str> = radios.iter().map(|radio| radio.kind.as_str()).collect();
+    kinds.dedup();
+    if kinds.is_empty() {
+        return "No radios found".to_string();
+    }
+    kinds
+        .iter()
+        .map(|kind| {
+            format!(
+                "{}: {}",
+                kind_label(kind),
+                radio_state(radios, kind).describe()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+fn read_flag(path: &std::path::Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|value| value.trim() == "1")
+}
+
+/// Reads all radios from /sys/class/rfkill
+pub fn read_radios() -> Vec<Radio> {
+    let Ok(entries) = fs::read_dir("/sys/class/rfkill") else {
+        return Vec::new();
+    };
+
+    let mut radios: Vec<Radio> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let kind = fs::read_to_string(path.join("type")).ok()?;
+            let name = fs::read_to_string(path.join("name")).unwrap_or_default();
+            Some(Radio {
+                name: name.trim().to_string(),
+                kind: kind.trim().to_string(),
+                soft_blocked: read_flag(&path.join("soft")),
+                hard_blocked: read_flag(&path.join("hard")),
+            })
+        })
+        .collect();
+    radios.sort_by(|a, b| (&a.kind, &a.name).cmp(&(&b.kind, &b.name)));
+    radios
+}
+
+/// Aggregates the state of all radios of the given type
+pub fn radio_state(radios: &[Radio], kind: &str) -> RadioState {
+    radios
+        .iter()
+        .filter(|radio| radio.kind == kind)
+        .fold(RadioState::default(), |state, radio| RadioState {
+            present: true,
+            soft_blocked: state.soft_blocked || radio.soft_blocked,
+            hard_blocked: state.hard_blocked || radio.hard_blocked,
+        })
+}
+
+/// Airplane mode is on when every radio is blocked
+pub fn is_airplane_mode(radios: &[Radio]) -> bool {
+    !radios.is_empty()
+        && radios
+            .iter()
+            .all(|radio| radio.soft_blocked || radio.hard_blocked)
+}
+
+/// Soft blocks or unblocks every radio through /dev/rfkill.
+/// logind grants the active session access to the device, so no root is needed.
+pub fn set_airplane_mode(enabled: bool) -> io::Result<()> {
+    // struct rfkill_event { __u32 idx; __u8 type; __u8 op; __u8 soft; __u8 hard; }
+    let event = [
+        0,
+        0,
+        0,
+        0,
+        RFKILL_TYPE_ALL,
+        RFKILL_OP_CHANGE_ALL,
+        enabled as u8,
+        0,
+    ];
+    fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/rfkill")?
+        .write_all(&event)
+}
@@ -0,0 +1,150 @@
+use crate::config::SessionConfig;
+use crate::connectivity::ToggleError;
+use niri_ipc::socket::Socket;
+use niri_ipc::{Request, Response};
+use std::process::Command;
+use zbus::blocking::Connection;
+
+/// Something the session menu can do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionAction {
+    Lock,
+    Suspend,
+    Hibernate,
+    LogOut,
+    Reboot,
+    PowerOff,
+}
+
+impl SessionAction {
+    pub const ALL: [SessionAction; 6] = [
+        SessionAction::Lock,
+        SessionAction::Suspend,
+        SessionAction::Hibernate,
+        SessionAction::LogOut,
+        SessionAction::Reboot,
+        SessionAction::PowerOff,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SessionAction::Lock => "Lock",
+            SessionAction::Suspend => "Suspend",
+            SessionAction::Hibernate => "Hibernate",
+            SessionAction::LogOut => "Log Out",
+            SessionAction::Reboot => "Restart",
+            SessionAction::PowerOff => "Power Off",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            SessionAction::Lock => "system-lock-screen-symbolic",
+            SessionAction::Suspend => "weather-clear-night-symbolic",
+            SessionAction::Hibernate => "drive-harddisk-symbolic",
+            SessionAction::LogOut => "system-log-out-symbolic",
+            SessionAction::Reboot => "system-reboot-symbolic",
+            SessionAction::PowerOff => "system-shutdown-symbolic",
+        }
+    }
+
+    /// Locking is harmless, everything else asks first
+    pub fn needs_confirmation(&self) -> bool {
+        *self != SessionAction::Lock
+    }
+
+    pub fn countdown_message(&self, seconds: u32) -> String {
+        match self {
+            SessionAction::Lock => format!("The screen will lock in {} seconds.", seconds),
+            SessionAction::Suspend => format!("The computer will suspend in {} seconds.", seconds),
+            SessionAction::Hibernate => {
+                format!("The computer will hibernate in {} seconds.", seconds)
+            }
+            SessionAction::LogOut => format!("You will be logged out in {} seconds.", seconds),
+            SessionAction::Reboot => format!("The computer will restart in {} seconds.", seconds),
+            SessionAction::PowerOff => {
+                format!("The computer will power off in {} seconds.", seconds)
+            }
+        }
+    }
+
+    /// logind Manager method performing this action, if it goes through logind
+    fn logind_method(&self) -> Option<&'static str> {
+        match self {
+            SessionAction::Suspend => Some("Suspend"),
+            SessionAction::Hibernate => Some("Hibernate"),
+            SessionAction::Reboot => Some("Reboot"),
+            SessionAction::PowerOff => Some("PowerOff"),
+            SessionAction::Lock | SessionAction::LogOut => None,
+        }
+    }
+
+    /// Asks logind's Can* methods whether the action is allowed on this machine
+    pub fn is_available(&self) -> bool {
+        let Some(method) = self.logind_method() else {
+            return true;
+        };
+        let answer = Connection::system().and_then(|conn| {
+            login_manager(&conn)?.call::<_, _, String>(format!("Can{}", method).as_str(), &())
+        });
+        // "challenge" means polkit will ask for authentication, which is fine
+        matches!(answer.as_deref(), Ok("yes") | Ok("challenge"))
+    }
+
+    /// Blocks while polkit asks for authentication, so call it off the main thread
+    pub fn perform(&self, config: &SessionConfig) -> Result<(), ToggleError> {
+        match self {
+            SessionAction::Lock => lock(config),
+            SessionAction::LogOut => quit_niri(),
+            _ => {
+                let method = self.logind_method().unwrap_or_default();
+                let conn = Connection::system()?;
+                // interactive = true lets polkit prompt if needed
+                login_manager(&conn)?.call::<_, _, ()>(method, &(true,))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+fn login_manager(conn: &Connection) -> zbus::Result<zbus::blocking::Proxy<'static>> {
+    zbus::blocking::Proxy::new(
+        conn,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+    )
+}
+
+fn lock(config: &SessionConfig) -> Result<(), ToggleError> {
+    if let Some(command) = &config.lock_command {
+        let mut locker = Command::new("sh").arg("-c").arg(command).spawn()?;
+        // Lockers run until the session is unlocked, reap them after that
+        std::thread::spawn(move || locker.wait());
+        return Ok(());
+    }
+
+    let conn = Connection::system()?;
+    let session = zbus::blocking::Proxy::new(
+        &conn,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1/session/auto",
+        "org.freedesktop.login1.Session",
+    )?;
+    let id: String = session.get_property("Id")?;
+    login_manager(&conn)?.call::<_, _, ()>("LockSession", &(id,))?;
+    Ok(())
+}
+
+fn quit_niri() -> Result<(), ToggleError> {
+    let reply = Socket::connect()?.send(Request::Action(niri_ipc::Action::Quit {
+        skip_confirmation: true,
+    }))?;
+    match reply {
+        Ok(Response::Handled) => Ok(()),
+        Ok(_) => Err(ToggleError::Niri(
+            "Unexpected response from niri".to_string(),
+        )),
+        Err(e) => Err(ToggleError::Niri(e)),
+    }
+}
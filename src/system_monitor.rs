@@ -6,21 +6,17 @@ pub fn read_cpu_usage() -> String {
         Ok(content) => {
             if let Some(line) = content.lines().next() {
                 let values: Vec<&str> = line.split_whitespace().collect();
-                if values.len() > 4 {
-                    if let (Ok(user), Ok(nice), Ok(system), Ok(idle)) = (
+                if values.len() > 4
+                    && let (Ok(user), Ok(nice), Ok(system), Ok(idle)) = (
                         values[1].parse::<u64>(),
                         values[2].parse::<u64>(),
                         values[3].parse::<u64>(),
                         values[4].parse::<u64>(),
-                    ) {
-                        let total = user + nice + system + idle;
-                        let usage = if total > 0 {
-                            ((total - idle) * 100) / total
-                        } else {
-                            0
-                        };
-                        return format!("{}%", usage);
-                    }
+                    )
+                {
+                    let total = user + nice + system + idle;
+                    let usage = ((total - idle) * 100).checked_div(total).unwrap_or(0);
+                    return format!("{}%", usage);
                 }
             }
             "N/A".to_string()
@@ -0,0 +1,142 @@
+use crate::config::{ColorScheme, ThemeConfig, ThemePreset, default_config_path, expand_home};
+use relm4::adw;
+use relm4::gtk;
+
+/// The variables style.css is written against, set on the bar window so user
+/// stylesheets can override them the same way
+const ADWAITA: &str = "
+window.main-bar-window {
+    --bar-bg-color: var(--window-bg-color);
+    --bar-fg-color: var(--window-fg-color);
+    --bar-accent-color: var(--accent-color);
+    --bar-text-shadow: none;
+}
+";
+
+const BLACK: &str = "
+window.main-bar-window {
+    --bar-bg-color: #000000;
+    --bar-fg-color: #ffffff;
+    --bar-accent-color: #ffffff;
+    --bar-text-shadow: 0 1px 1px rgba(0, 0, 0, 0.3);
+}
+";
+
+const ACCENT: &str = "
+window.main-bar-window {
+    --bar-bg-color: var(--accent-bg-color);
+    --bar-fg-color: var(--accent-fg-color);
+    --bar-accent-color: var(--accent-fg-color);
+    --bar-text-shadow: none;
+}
+";
+
+/// Loads the bar's style with the chosen preset and the user's stylesheet. Adwaita's
+/// colour variables follow the style manager, so dark, high contrast and accent changes
+/// apply without reloading.
+pub fn apply(config: &ThemeConfig) {
+    adw::StyleManager::default().set_color_scheme(match config.color_scheme {
+        ColorScheme::System => adw::ColorScheme::Default,
+        ColorScheme::Light => adw::ColorScheme::ForceLight,
+        ColorScheme::Dark => adw::ColorScheme::ForceDark,
+    });
+
+    let display = gtk::gdk::Display::default().expect("Could not get default display");
+    let preset = match config.preset {
+        ThemePreset::Adwaita => ADWAITA,
+        ThemePreset::Black => BLACK,
+        ThemePreset::Accent => ACCENT,
+    };
+    let css_provider = gtk::CssProvider::new();
+    css_provider.connect_parsing_error(|_, section, error| {
+        eprintln!("Warning: Invalid CSS at {}: {}", section.to_str(), error);
+    });
+    css_provider.load_from_string(&format!(
+        "{}{}{}",
+        preset,
+        include_str!("style.css"),
+        options_css(config)
+    ));
+    gtk::style_context_add_provider_for_display(
+        &display,
+        &css_provider,
+        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+
+    let path = match &config.stylesheet {
+        Some(path) => expand_home(path),
+        None => default_config_path().with_file_name("style.css"),
+    };
+    if !path.exists() {
+        if config.stylesheet.is_some() {
+            eprintln!("Warning: Stylesheet {} not found", path.display());
+        }
+        return;
+    }
+    let user_provider = gtk::CssProvider::new();
+    user_provider.connect_parsing_error(|_, section, error| {
+        eprintln!("Warning: Invalid CSS at {}: {}", section.to_str(), error);
+    });
+    user_provider.load_from_path(&path);
+    gtk::style_context_add_provider_for_display(
+        &display,
+        &user_provider,
+        gtk::STYLE_PROVIDER_PRIORITY_USER,
+    );
+}
+
+/// Rules for the `[theme]` opacity, radius, pills and module colours, coming after
+/// style.css so they win over it
+fn options_css(config: &ThemeConfig) -> String {
+    let mut css = String::new();
+    let background = if config.opacity < 1.0 {
+        format!(
+            "color-mix(in srgb, var(--bar-bg-color) {}%, transparent)",
+            (config.opacity.max(0.0) * 100.0).round()
+        )
+    } else {
+        "var(--bar-bg-color)".to_string()
+    };
+    if config.pills {
+        css.push_str(
+            "window.main-bar-window .main-container { background-color: transparent; }
+window.main-bar-window .bar-section { padding: 0 6px; }
+window.main-bar-window.vertical .bar-section { padding: 6px 0; }
+",
+        );
+    }
+    if config.pills || config.opacity < 1.0 || config.radius > 0 {
+        let surface = if config.pills {
+            ".bar-section"
+        } else {
+            ".main-container"
+        };
+        css.push_str(&format!(
+            "window.main-bar-window {} {{ background-color: {}; border-radius: {}px; }}\n",
+            surface, background, config.radius
+        ));
+    }
+    for (module, color) in &config.module_colors {
+        css.push_str(&format!(
+            "window.main-bar-window {} {{ background-color: {}; }}\n",
+            module_selector(module),
+            color
+        ));
+    }
+    css
+}
+
+/// CSS class of a module named in `module_colors`, any other name is a custom module id
+fn module_selector(module: &str) -> String {
+    match module {
+        "workspaces" => ".workspace-section".to_string(),
+        "clock" => ".clock-container".to_string(),
+        "timer" => ".timer-container".to_string(),
+        "media" => ".media-button".to_string(),
+        "brightness" => ".brightness-container".to_string(),
+        "volume" => ".volume-button".to_string(),
+        "notifications" => ".notifications-button".to_string(),
+        "system" => ".system-info-button".to_string(),
+        id => format!(".custom-{}", id),
+    }
+}
@@ -0,0 +1,194 @@
+use crate::config::TimerConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimerKind {
+    Countdown,
+    Pomodoro,
+    Stopwatch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PomodoroPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl PomodoroPhase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PomodoroPhase::Work => "Focus",
+            PomodoroPhase::ShortBreak => "Short break",
+            PomodoroPhase::LongBreak => "Long break",
+        }
+    }
+}
+
+/// A running or paused timer. Times are wall clock seconds so it survives restarts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timer {
+    pub kind: TimerKind,
+    /// When the timer was last started or resumed, None while paused
+    resumed_at: Option<u64>,
+    /// Seconds counted before the last resume
+    elapsed_before: u64,
+    /// Length of the countdown or the current pomodoro phase in seconds
+    duration: u64,
+    pub phase: PomodoroPhase,
+    /// Focus phases finished in this pomodoro cycle
+    pub completed: u32,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+impl Timer {
+    fn start(kind: TimerKind, duration: u64) -> Self {
+        Self {
+            kind,
+            resumed_at: Some(now()),
+            elapsed_before: 0,
+            duration,
+            phase: PomodoroPhase::Work,
+            completed: 0,
+        }
+    }
+
+    pub fn countdown(minutes: u32) -> Self {
+        Self::start(TimerKind::Countdown, u64::from(minutes) * 60)
+    }
+
+    pub fn pomodoro(config: &TimerConfig) -> Self {
+        Self::start(TimerKind::Pomodoro, u64::from(config.work_minutes) * 60)
+    }
+
+    pub fn stopwatch() -> Self {
+        Self::start(TimerKind::Stopwatch, 0)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.resumed_at.is_some()
+    }
+
+    pub fn elapsed(&self) -> u64 {
+        self.elapsed_before
+            + self
+                .resumed_at
+                .map_or(0, |resumed_at| now().saturating_sub(resumed_at))
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.duration.saturating_sub(self.elapsed())
+    }
+
+    /// Stopwatches never finish
+    pub fn is_finished(&self) -> bool {
+        self.kind != TimerKind::Stopwatch && self.remaining() == 0
+    }
+
+    pub fn pause(&mut self) {
+        self.elapsed_before = self.elapsed();
+        self.resumed_at = None;
+    }
+
+    pub fn resume(&mut self) {
+        if self.resumed_at.is_none() {
+            self.resumed_at = Some(now());
+        }
+    }
+
+    /// Moves a pomodoro on to its next phase, every `long_break_every`th break is a long one
+    pub fn next_phase(&mut self, config: &TimerConfig) {
+        let (phase, minutes) = match self.phase {
+            PomodoroPhase::Work => {
+                self.completed += 1;
+                if self
+                    .completed
+                    .is_multiple_of(config.long_break_every.max(1))
+                {
+                    (PomodoroPhase::LongBreak, config.long_break_minutes)
+                } else {
+                    (PomodoroPhase::ShortBreak, config.break_minutes)
+                }
+            }
+            PomodoroPhase::ShortBreak | PomodoroPhase::LongBreak => {
+                (PomodoroPhase::Work, config.work_minutes)
+            }
+        };
+        self.phase = phase;
+        self.duration = u64::from(minutes) * 60;
+        self.elapsed_before = 0;
+        self.resumed_at = Some(now());
+    }
+
+    /// Remaining time for countdowns, elapsed time for stopwatches
+    pub fn display(&self) -> String {
+        let seconds = match self.kind {
+            TimerKind::Stopwatch => self.elapsed(),
+            _ => self.remaining(),
+        };
+        if seconds >= 3600 {
+            format!(
+                "{}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            )
+        } else {
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        }
+    }
+
+    /// e.g. "Focus · 2 done" for pomodoros
+    pub fn description(&self) -> String {
+        match self.kind {
+            TimerKind::Countdown => "Countdown".to_string(),
+            TimerKind::Stopwatch => "Stopwatch".to_string(),
+            TimerKind::Pomodoro => format!("{} · {} done", self.phase.label(), self.completed),
+        }
+    }
+}
+
+/// Where the running timer is kept between restarts, under $XDG_STATE_HOME
+fn state_path() -> PathBuf {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("state"))
+        })
+        .unwrap_or_default();
+    state_dir.join("adwbar").join("timer.json")
+}
+
+pub fn load_timer() -> Option<Timer> {
+    let content = fs::read_to_string(state_path()).ok()?;
+    serde_json::from_str(&content)
+        .inspect_err(|e| eprintln!("Warning: Ignoring saved timer: {}", e))
+        .ok()
+}
+
+/// Saves the timer, or forgets the saved one when there is none
+pub fn save_timer(timer: Option<&Timer>) {
+    let path = state_path();
+    let result = match timer {
+        Some(timer) => path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, serde_json::to_string(timer).unwrap_or_default())),
+        None => match fs::remove_file(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+    };
+    if let Err(e) = result {
+        eprintln!("Warning: Could not save timer to {}: {}", path.display(), e);
+    }
+}
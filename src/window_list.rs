@@ -4,12 +4,15 @@ use niri_ipc::{Request, Response};
 use relm4::gtk::Image;
 use std::collections::BTreeMap;
 
-/// Updates the window list container with current windows from niri
-pub fn update_window_list(container: &relm4::gtk::Box) {
+/// App icons and ids of the open windows, grouped by workspace
+pub type Workspaces = BTreeMap<u64, Vec<(String, u64)>>;
+
+/// Reads the current windows from niri
+pub fn read_workspaces() -> Option<Workspaces> {
     match Socket::connect().and_then(|mut s| s.send(Request::Windows)) {
         Ok(Ok(Response::Windows(windows))) => {
             // Group windows by workspace
-            let mut workspaces: BTreeMap<u64, Vec<(String, u64)>> = BTreeMap::new();
+            let mut workspaces: Workspaces = BTreeMap::new();
 
             for window in windows {
                 let workspace_id = window.workspace_id.unwrap_or(0);
@@ -19,28 +22,44 @@ pub fn update_window_list(container: &relm4::gtk::Box) {
                 let window_id = window.id;
                 workspaces
                     .entry(workspace_id)
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push((app_id, window_id));
             }
+            Some(workspaces)
+        }
+        Ok(_) => {
+            eprintln!("Unexpected response from niri");
+            None
+        }
+        Err(e) => {
+            eprintln!("Failed to get windows: {}", e);
+            None
+        }
+    }
+}
 
-            // Clear existing widgets
-            while let Some(child) = container.first_child() {
-                container.remove(&child);
-            }
+/// Updates the window list container with the given windows, laid out in the container's
+/// orientation
+pub fn update_window_list(container: &relm4::gtk::Box, workspaces: &Workspaces) {
+    // Clear existing widgets
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
 
-            // Build new workspace sections
-            for (workspace_id, windows) in workspaces {
-                let workspace_box = create_workspace_section(workspace_id, windows);
-                container.append(&workspace_box);
-            }
-        }
-        Ok(_) => eprintln!("Unexpected response from niri"),
-        Err(e) => eprintln!("Failed to get windows: {}", e),
+    // Build new workspace sections
+    for (workspace_id, windows) in workspaces {
+        let workspace_box =
+            create_workspace_section(*workspace_id, windows, container.orientation());
+        container.append(&workspace_box);
     }
 }
 
-fn create_workspace_section(workspace_id: u64, windows: Vec<(String, u64)>) -> relm4::gtk::Box {
-    let workspace_box = relm4::gtk::Box::new(relm4::gtk::Orientation::Horizontal, 3);
+fn create_workspace_section(
+    workspace_id: u64,
+    windows: &[(String, u64)],
+    orientation: relm4::gtk::Orientation,
+) -> relm4::gtk::Box {
+    let workspace_box = relm4::gtk::Box::new(orientation, 3);
     workspace_box.add_css_class("workspace-section");
 
     // Add a workspace label
@@ -50,7 +69,7 @@ fn create_workspace_section(workspace_id: u64, windows: Vec<(String, u64)>) -> r
 
     // Add app icon buttons
     for (app_id, window_id) in windows {
-        let button = create_window_button(&app_id, window_id);
+        let button = create_window_button(app_id, *window_id);
         workspace_box.append(&button);
     }
 